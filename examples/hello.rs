@@ -9,7 +9,7 @@ impl EventHandler for GameState {
 
     fn input(&mut self, _: InputEvent) {}
 
-    fn update(&mut self, _: f32) {}
+    fn update(&mut self, _: &mut GfxContext, _: f32) {}
 
     fn redraw(&mut self, g: &mut GfxContext) {
         g.clear_color(Color::BLACK);
@@ -21,6 +21,6 @@ impl EventHandler for GameState {
     }
 }
 
-fn main() {
-    pollster::block_on(App::new(GameState {})).run();
+fn main() -> Result<(), ErrorKind> {
+    pollster::block_on(App::new(GameState {}))?.run()
 }
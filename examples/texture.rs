@@ -1,23 +1,31 @@
 use kaffee::prelude::*;
 
-struct GameState;
+#[derive(Default)]
+struct GameState {
+    atlas: Option<TextureRef>,
+}
 
 impl EventHandler for GameState {
     fn init(&mut self, g: &mut GfxContext) -> Result<(), ErrorKind> {
-        let _ = g.create_texture("./res/textures/atlas.png", FilterMode::Nearest)?;
+        self.atlas = Some(g.create_texture("./res/textures/atlas.png", FilterMode::Nearest)?);
         Ok(())
     }
 
     fn input(&mut self, _: InputEvent) {}
 
-    fn update(&mut self, _: f32) {}
+    fn update(&mut self, _: &mut GfxContext, _: f32) {}
 
     fn redraw(&mut self, g: &mut GfxContext) {
         g.clear_color(Color::BLACK);
-        g.draw_quad(-0.5, -0.5, 1., Color::RED);
+
+        if let Some(atlas) = &self.atlas {
+            g.draw_texture(100., 100., 256., 256., Color::WHITE, atlas);
+        }
+
+        g.draw_quad(400., 100., 100., Color::RED);
     }
 }
 
-fn main() {
-    pollster::block_on(App::new(GameState {})).run();
+fn main() -> Result<(), ErrorKind> {
+    pollster::block_on(App::new(GameState::default()))?.run()
 }
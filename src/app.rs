@@ -4,7 +4,7 @@
 //!
 //! The following example demonstrates the setup of an application:
 //!
-//! ```
+//! ```no_run
 //! use kaffee::prelude::*;
 //!
 //! struct GameState;
@@ -15,105 +15,502 @@
 //!     }
 //!
 //!     fn input(&mut self, _: InputEvent) {}
-//!     fn update(&mut self, dt: f32) {}
+//!     fn update(&mut self, g: &mut GfxContext, dt: f32) {}
 //!     fn redraw(&mut self, g: &mut GfxContext) {}
 //! }
 //!
-//! fn main() {
-//!     pollster::block_on(App::new(GameState {})).run();
+//! fn main() -> Result<(), ErrorKind> {
+//!     pollster::block_on(App::new(GameState {}))?.run()
 //! }
 //! ```
 //!
+//! Creating the application fails if no window or compatible graphics adapter is available. To handle
+//! errors which occur after the application was created, use [`App::run_return`] where it is supported.
+//!
+
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
 
+use glam::Vec2;
 use winit::{
-    dpi::LogicalSize,
-    event::{Event, WindowEvent},
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{ElementState, Event, ModifiersState, MouseButton, TouchPhase, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
-    window::{Window, WindowBuilder},
 };
 
 use crate::{
-    config::Config,
-    event::EventHandler,
+    config::{Config, RedrawMode},
+    error::ErrorKind,
+    event::{self, EventHandler},
     gfx::GfxContext,
-    input::{InputEvent, KeyEvent},
-    prelude::BatchExt,
+    input::{
+        recording::RecordedFrame, CursorPosition, GamepadBackend, Gamepads, GestureRecognizer,
+        InputEvent, KeyEvent, RecordedEvent, Recorder, Recording,
+    },
+    time::{Clock, Stepper},
+    window,
 };
 
+/// How often gamepads and gestures are polled while waiting for input in [`RedrawMode::Reactive`].
+const POLL_INTERVAL: Duration = Duration::from_millis(16);
+
+/// How often newly connected gamepads are looked for while waiting for input in [`RedrawMode::Reactive`].
+const CONNECT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The touch id used for touches emulated from the mouse.
+const MOUSE_TOUCH_ID: u64 = u64::MAX;
+
 /// A `kaffee` application.
 pub struct App<H: 'static + EventHandler> {
-    window: Window,
     event_loop: EventLoop<()>,
+    state: AppState<H>,
+}
+
+/// Everything the event loop needs access to while it is running.
+struct AppState<H: EventHandler> {
     event_handler: H,
     gfx_ctx: GfxContext,
+    clock: Clock,
+    stepper: Stepper,
+    next_frame: Instant,
+    needs_redraw: bool,
+    /// Whether the event loop waited for input without drawing, see [`RedrawMode::Reactive`].
+    idle: bool,
+    modifiers: ModifiersState,
+    gamepads: Gamepads,
+    gestures: GestureRecognizer,
+    emulate_touch: bool,
+    /// The last position of the touch emulated from the mouse, while the left button is held.
+    mouse_touch: Option<CursorPosition>,
+    suspended: bool,
+    recorder: Option<Recorder>,
+    /// The remaining frames of the recording being replayed.
+    replay: Option<std::vec::IntoIter<RecordedFrame>>,
+    /// The error which stopped the event loop.
+    error: Option<ErrorKind>,
 }
 
 impl<H: 'static + EventHandler> App<H> {
-    pub async fn new(event_handler: H) -> Self {
+    pub async fn new(event_handler: H) -> Result<Self, ErrorKind> {
         Self::with_config(event_handler, &Config::default()).await
     }
 
-    pub async fn with_config(event_handler: H, config: &Config) -> Self {
+    /// Creates the window and graphics context. This fails if the window could not be created
+    /// or no compatible graphics adapter was found.
+    pub async fn with_config(event_handler: H, config: &Config) -> Result<Self, ErrorKind> {
         let event_loop = EventLoop::new();
 
-        let window = WindowBuilder::new()
-            .with_title(&config.title)
-            .with_inner_size(LogicalSize::new(config.width, config.height))
-            .with_resizable(config.resizable)
-            .build(&event_loop)
-            .expect("Failed to create window with given settings");
+        let window = window::build(config, &event_loop)?;
 
-        let gfx_ctx = match GfxContext::new(&window, config).await {
-            Ok(ctx) => ctx,
-            Err(why) => panic!("Failed to create GfxContext: {:?}", why),
-        };
+        let gfx_ctx = GfxContext::new(window, config).await?;
 
-        Self {
-            window,
+        Ok(Self {
             event_loop,
-            event_handler,
-            gfx_ctx,
+            state: AppState {
+                event_handler,
+                gfx_ctx,
+                clock: Clock::new(),
+                stepper: Stepper::new(config.timestep)?,
+                next_frame: Instant::now(),
+                needs_redraw: true,
+                idle: false,
+                modifiers: ModifiersState::empty(),
+                gamepads: Gamepads::new(config.gamepad_deadzone),
+                gestures: GestureRecognizer::new(config.gestures),
+                emulate_touch: config.emulate_touch,
+                mouse_touch: None,
+                suspended: false,
+                recorder: None,
+                replay: None,
+                error: None,
+            },
+        })
+    }
+
+    /// Replaces the default gamepad backend, e.g. with [`VirtualGamepads`](crate::prelude::VirtualGamepads).
+    pub fn with_gamepad_backend(mut self, backend: impl GamepadBackend + 'static) -> Self {
+        self.state.gamepads.set_backend(Box::new(backend));
+        self
+    }
+
+    /// Records all input events and frame deltas to a file, which can be loaded as a [`Recording`].
+    /// Every frame is written immediately, so the recording is preserved even if the application crashes.
+    pub fn record<P>(mut self, path: P) -> Result<Self, ErrorKind>
+    where
+        P: AsRef<Path>,
+    {
+        self.state.recorder = Some(Recorder::create(path)?);
+        Ok(self)
+    }
+
+    /// Replays a [`Recording`] instead of live input. Live input is ignored until all frames were replayed.
+    pub fn replay(mut self, recording: Recording) -> Self {
+        self.state.replay = Some(recording.into_frames());
+        self
+    }
+
+    /// Runs the application until the window is closed. If [`EventHandler::init`] fails, or an error occurs while running,
+    /// the error is logged and the process exits with code `1`.
+    pub fn run(self) -> ! {
+        let Self {
+            event_loop,
+            mut state,
+        } = self;
+
+        if let Err(why) = state.init() {
+            log::error!("Failed to initialize application: {why}");
+            std::process::exit(1);
+        }
+
+        event_loop.run(move |event, _, control_flow| {
+            state.handle_event(event, control_flow);
+
+            if let Some(why) = state.error.take() {
+                log::error!("{why}");
+            }
+        })
+    }
+
+    /// Runs the application until the window is closed and returns the error which stopped it, if any.
+    /// Unlike [`App::run`], this returns control to the caller, but it is not supported on all platforms.
+    #[cfg(any(
+        target_os = "windows",
+        target_os = "macos",
+        target_os = "android",
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    pub fn run_return(self) -> Result<(), ErrorKind> {
+        use winit::platform::run_return::EventLoopExtRunReturn;
+
+        let Self {
+            mut event_loop,
+            mut state,
+        } = self;
+
+        state.init()?;
+        event_loop.run_return(|event, _, control_flow| state.handle_event(event, control_flow));
+
+        match state.error.take() {
+            Some(why) => Err(why),
+            None => Ok(()),
         }
     }
+}
+
+impl<H: EventHandler> AppState<H> {
+    fn init(&mut self) -> Result<(), ErrorKind> {
+        self.event_handler.init(&mut self.gfx_ctx)?;
+        self.clock = Clock::new();
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: Event<()>, control_flow: &mut ControlFlow) {
+        match event {
+            Event::LoopDestroyed => self.event_handler.shutdown(&mut self.gfx_ctx),
+            Event::Suspended => {
+                self.suspended = true;
+                self.event_handler.suspended(&mut self.gfx_ctx);
+            }
+            // Resumed is also sent once at startup, which is covered by `init`.
+            Event::Resumed if self.suspended => {
+                self.suspended = false;
+                self.needs_redraw = true;
+                // The time spent suspended is not part of the simulation.
+                self.clock.reset();
+                self.event_handler.resumed(&mut self.gfx_ctx);
+            }
+            Event::MainEventsCleared => {
+                while let Some(event) = self.gamepads.poll() {
+                    self.input(InputEvent::Gamepad(event));
+                }
 
-    pub fn run(mut self) -> ! {
-        self.event_handler
-            .init(&mut self.gfx_ctx)
-            .expect("Failed to initialize application");
-
-        self.event_loop.run(move |event, _, control_flow| {
-            *control_flow = ControlFlow::Poll;
-
-            match event {
-                Event::LoopDestroyed => {
-                    return;
-                }
-                Event::RedrawRequested(_) => {
-                    self.event_handler.update(1.);
-                    self.event_handler.redraw(&mut self.gfx_ctx);
-                    self.gfx_ctx.end_frame();
-                }
-                Event::WindowEvent { ref event, .. } => match event {
-                    WindowEvent::KeyboardInput {
-                        device_id: _,
-                        input,
-                        is_synthetic: _,
-                    } => match input.virtual_keycode {
-                        None => (),
-                        Some(key_code) => {
-                            self.event_handler.input(InputEvent::Key(KeyEvent {
-                                state: input.state,
-                                key: key_code,
-                            }));
+                self.gestures.update(Instant::now());
+                self.poll_gestures();
+
+                if !self.suspended && self.should_redraw() {
+                    if let Some(window) = self.gfx_ctx.window() {
+                        window.request_redraw();
+                    }
+                } else if self.gfx_ctx.redraw_mode() == RedrawMode::Reactive {
+                    self.idle = true;
+                }
+            }
+            Event::RedrawEventsCleared if !matches!(control_flow, ControlFlow::ExitWithCode(_)) => {
+                *control_flow = self.control_flow();
+            }
+            Event::RedrawRequested(_) => {
+                self.needs_redraw = false;
+
+                // The time spent waiting for input is not part of the simulation.
+                if self.idle {
+                    self.idle = false;
+                    self.clock.reset();
+                }
+
+                let mut dt = self.clock.tick();
+                if let Some(replay) = &mut self.replay {
+                    match replay.next() {
+                        Some(frame) => {
+                            for event in frame.events {
+                                match event {
+                                    RecordedEvent::Input(event) => self.dispatch(event),
+                                    RecordedEvent::Resized {
+                                        width,
+                                        height,
+                                        scale_factor,
+                                    } => self.replay_resize(width, height, scale_factor),
+                                }
+                            }
+                            dt = frame.dt;
+                            self.needs_redraw = true;
+                        }
+                        None => {
+                            log::info!("Replay finished");
+                            self.replay = None;
                         }
-                    },
-                    WindowEvent::CloseRequested => {
-                        *control_flow = ControlFlow::Exit;
                     }
-                    _ => (),
+                }
+
+                if let Some(recorder) = &mut self.recorder {
+                    if let Err(why) = recorder.record_frame(dt) {
+                        log::error!("Recording stopped: {why}");
+                        self.recorder = None;
+                    }
+                }
+
+                if let Err(why) = self
+                    .stepper
+                    .frame(&mut self.event_handler, &mut self.gfx_ctx, dt)
+                {
+                    self.error = Some(why);
+                    *control_flow = ControlFlow::ExitWithCode(1);
+                }
+            }
+            Event::WindowEvent { ref event, .. } => match event {
+                WindowEvent::KeyboardInput {
+                    device_id: _,
+                    input,
+                    is_synthetic: _,
+                } => match input.virtual_keycode {
+                    None => (),
+                    Some(key_code) => self.input(InputEvent::Key(KeyEvent {
+                        state: input.state,
+                        key: key_code,
+                        modifiers: self.modifiers,
+                    })),
                 },
+                WindowEvent::ModifiersChanged(modifiers) => self.modifiers = *modifiers,
+                WindowEvent::ReceivedCharacter(c) if !c.is_control() => {
+                    self.input(InputEvent::Text(*c));
+                }
+                WindowEvent::Ime(ime) => self.input(InputEvent::Ime(ime.clone().into())),
+                WindowEvent::CursorMoved { position, .. } => {
+                    let position = self.cursor_position(*position);
+                    self.input(InputEvent::MouseMoved { position });
+
+                    if self.mouse_touch.is_some() {
+                        self.mouse_touch = Some(position);
+                        self.touch(MOUSE_TOUCH_ID, TouchPhase::Moved, position);
+                    }
+                }
+                WindowEvent::MouseInput { state, button, .. } => {
+                    self.input(InputEvent::MouseButton {
+                        button: *button,
+                        state: *state,
+                    });
+
+                    if self.emulate_touch && *button == MouseButton::Left {
+                        match state {
+                            ElementState::Pressed => {
+                                if let Some(position) = self.gfx_ctx.input().cursor_position() {
+                                    self.mouse_touch = Some(position);
+                                    self.touch(MOUSE_TOUCH_ID, TouchPhase::Started, position);
+                                }
+                            }
+                            ElementState::Released => self.end_mouse_touch(TouchPhase::Ended),
+                        }
+                    }
+                }
+                WindowEvent::Touch(touch) => {
+                    let position = self.cursor_position(touch.location);
+                    self.touch(touch.id, touch.phase, position);
+                }
+                WindowEvent::MouseWheel { delta, .. } => {
+                    self.input(InputEvent::MouseWheel { delta: *delta });
+                }
+                WindowEvent::CursorEntered { .. } => self.input(InputEvent::CursorEntered),
+                WindowEvent::CursorLeft { .. } => self.input(InputEvent::CursorLeft),
+                WindowEvent::Focused(focused) => {
+                    self.input(InputEvent::Focused(*focused));
+                    // The button release will not be reported while the window is unfocused.
+                    if !focused {
+                        self.end_mouse_touch(TouchPhase::Cancelled);
+                    }
+                    self.event_handler
+                        .focus_changed(&mut self.gfx_ctx, *focused);
+                }
+                WindowEvent::Resized(size) => {
+                    self.resize(size.width, size.height, self.gfx_ctx.scale_factor());
+                }
+                WindowEvent::ScaleFactorChanged {
+                    scale_factor,
+                    new_inner_size,
+                } => {
+                    self.resize(new_inner_size.width, new_inner_size.height, *scale_factor);
+                }
+                WindowEvent::CloseRequested
+                    if self.event_handler.quit_requested(&mut self.gfx_ctx) =>
+                {
+                    self.gfx_ctx.quit();
+                }
                 _ => (),
+            },
+            _ => (),
+        }
+
+        if self.gfx_ctx.should_quit() && !matches!(control_flow, ControlFlow::ExitWithCode(_)) {
+            *control_flow = ControlFlow::Exit;
+        }
+    }
+
+    /// Handles live input, which is ignored while a recording is replayed.
+    fn input(&mut self, event: InputEvent) {
+        if self.replay.is_none() {
+            self.dispatch(event);
+        }
+    }
+
+    fn dispatch(&mut self, event: InputEvent) {
+        self.needs_redraw = true;
+
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_event(&event);
+        }
+
+        event::dispatch(&mut self.event_handler, &mut self.gfx_ctx, event);
+    }
+
+    fn touch(&mut self, id: u64, phase: TouchPhase, position: CursorPosition) {
+        self.input(InputEvent::Touch {
+            id,
+            phase,
+            position,
+        });
+
+        self.gestures
+            .touch(id, phase, position.logical, Instant::now());
+        self.poll_gestures();
+    }
+
+    /// Ends the touch emulated from the mouse. The cursor may have left the window since it was last seen,
+    /// so the last known position is used.
+    fn end_mouse_touch(&mut self, phase: TouchPhase) {
+        if let Some(last) = self.mouse_touch.take() {
+            let position = self.gfx_ctx.input().cursor_position().unwrap_or(last);
+            self.touch(MOUSE_TOUCH_ID, phase, position);
+        }
+    }
+
+    fn poll_gestures(&mut self) {
+        while let Some(gesture) = self.gestures.poll() {
+            self.input(InputEvent::Gesture(gesture));
+        }
+    }
+
+    fn cursor_position(&self, position: PhysicalPosition<f64>) -> CursorPosition {
+        let logical = position.to_logical::<f32>(self.gfx_ctx.scale_factor());
+
+        CursorPosition {
+            physical: Vec2::new(position.x as f32, position.y as f32),
+            logical: Vec2::new(logical.x, logical.y),
+        }
+    }
+
+    fn resize(&mut self, width: u32, height: u32, scale_factor: f64) {
+        // During a replay, the handler only sees the recorded resizes, including the ones the replay applies to the window.
+        // The surface still has to match the actual window size.
+        if self.replay.is_some() {
+            let scale_factor = self.gfx_ctx.scale_factor();
+            self.gfx_ctx.resize(width, height, scale_factor);
+            self.needs_redraw = true;
+            return;
+        }
+
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_resize(width, height, scale_factor);
+        }
+
+        self.needs_redraw = true;
+        event::resize(
+            &mut self.event_handler,
+            &mut self.gfx_ctx,
+            width,
+            height,
+            scale_factor,
+        );
+    }
+
+    /// Applies a recorded resize. The window is resized as well, so that the surface keeps matching it.
+    fn replay_resize(&mut self, width: u32, height: u32, scale_factor: f64) {
+        if let Some(window) = self.gfx_ctx.window() {
+            window.set_inner_size(PhysicalSize::new(width, height));
+        }
+
+        event::resize(
+            &mut self.event_handler,
+            &mut self.gfx_ctx,
+            width,
+            height,
+            scale_factor,
+        );
+    }
+
+    /// Decides whether a new frame should be requested, according to the [`RedrawMode`].
+    fn should_redraw(&mut self) -> bool {
+        match self.gfx_ctx.redraw_mode() {
+            RedrawMode::Continuous => true,
+            RedrawMode::Capped(fps) => {
+                let now = Instant::now();
+                if now < self.next_frame {
+                    return false;
+                }
+
+                let interval = Duration::from_secs_f64(1. / fps.max(1) as f64);
+                self.next_frame = (self.next_frame + interval).max(now);
+                true
             }
-        })
+            RedrawMode::Reactive => {
+                let requested = self.gfx_ctx.take_redraw_request();
+                self.needs_redraw || requested
+            }
+        }
+    }
+
+    fn control_flow(&self) -> ControlFlow {
+        // Nothing is drawn while suspended, so the event loop sleeps until it is resumed.
+        if self.suspended {
+            return ControlFlow::Wait;
+        }
+
+        match self.gfx_ctx.redraw_mode() {
+            RedrawMode::Continuous => ControlFlow::Poll,
+            RedrawMode::Capped(_) => ControlFlow::WaitUntil(self.next_frame),
+            RedrawMode::Reactive if self.gfx_ctx.redraw_requested() => ControlFlow::Poll,
+            // Gamepads and long presses do not wake up the event loop, so they have to be polled periodically.
+            RedrawMode::Reactive if self.gamepads.any_connected() || self.gestures.is_active() => {
+                ControlFlow::WaitUntil(Instant::now() + POLL_INTERVAL)
+            }
+            // Without a connected gamepad, polling less often is enough to notice one being plugged in.
+            RedrawMode::Reactive if self.gamepads.has_backend() => {
+                ControlFlow::WaitUntil(Instant::now() + CONNECT_POLL_INTERVAL)
+            }
+            RedrawMode::Reactive => ControlFlow::Wait,
+        }
     }
 }
@@ -1,10 +1,125 @@
+mod args;
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::ErrorKind,
+    fs,
+    gfx::{Backend, Features, Limits, PowerPreference, PresentMode},
+    input::GestureConfig,
+    time::Timestep,
+    window::Fullscreen,
+};
+
+/// The fields of [`Config`] which are stored as user settings. Other fields in the settings file are ignored.
+const USER_SETTINGS: &[&str] = &[
+    "width",
+    "height",
+    "fullscreen",
+    "present_mode",
+    "redraw_mode",
+];
+
+/// Determines when a new frame is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RedrawMode {
+    /// Redraws as often as possible.
+    #[default]
+    Continuous,
+    /// Redraws at most the given number of times per second and sleeps in between.
+    /// This can be combined with [`PresentMode::Immediate`](crate::prelude::PresentMode::Immediate) to limit the frame rate without vsync.
+    Capped(u32),
+    /// Redraws only after input was received or [`GfxContext::request_redraw`](crate::prelude::GfxContext::request_redraw) was called.
+    /// This keeps the CPU idle while nothing changes, which is useful for tools and editors.
+    ///
+    /// Gamepads do not wake up the application by themselves, so they are polled periodically while a gamepad backend
+    /// is available: frequently while a gamepad is connected, and a few times per second to notice new ones.
+    Reactive,
+}
+
 /// Application configuration
-#[derive(Debug)]
+///
+/// A configuration can be loaded from a TOML or RON file, overridden by the settings the user saved (such as the window size)
+/// and by command-line flags.
+/// Fields missing in a file keep their default values. GPU features, limits and gesture thresholds are requirements of
+/// the application rather than settings, so they are not read from or written to files.
+///
+/// # Example
+///
+/// ```
+/// use kaffee::prelude::*;
+///
+/// let config = Config::default()
+///     .with_args(["--width", "1280", "--height=720", "--fullscreen", "--no-vsync"])
+///     .unwrap();
+///
+/// assert_eq!((config.width, config.height), (1280, 720));
+/// assert_eq!(config.fullscreen, Fullscreen::Borderless);
+/// assert_eq!(config.present_mode, PresentMode::Immediate);
+/// ```
+///
+/// A game would typically load its configuration like this:
+///
+/// ```no_run
+/// # use kaffee::prelude::*;
+/// # fn main() -> Result<(), ErrorKind> {
+/// let config = Config::from_file("assets/config.toml")?
+///     .with_user_settings("my-game")?
+///     .with_args(std::env::args().skip(1))?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub title: String,
     pub width: u16,
     pub height: u16,
     pub resizable: bool,
+    /// Defaults to [`Fullscreen::Windowed`].
+    pub fullscreen: Fullscreen,
+    pub maximized: bool,
+    /// Shows the title bar and border of the window. Defaults to `true`.
+    pub decorations: bool,
+    /// The minimum size of the window in logical pixels.
+    pub min_size: Option<(u16, u16)>,
+    /// The maximum size of the window in logical pixels.
+    pub max_size: Option<(u16, u16)>,
+    /// Makes the window background transparent where the clear color and drawings are transparent.
+    /// This is not supported on every platform.
+    pub transparent: bool,
+    /// Keeps the window above all other windows.
+    pub always_on_top: bool,
+    /// The path of an image used as window icon.
+    pub icon: Option<PathBuf>,
+    /// Determines how often `update` is called per frame. Defaults to [`Timestep::Variable`].
+    pub timestep: Timestep,
+    /// Determines when a new frame is drawn. Defaults to [`RedrawMode::Continuous`].
+    pub redraw_mode: RedrawMode,
+    /// Determines how frames are presented. Defaults to [`PresentMode::Fifo`], i.e. vsync.
+    pub present_mode: PresentMode,
+    /// The graphics API used for rendering. Defaults to [`Backend::Auto`].
+    pub backend: Backend,
+    /// Defaults to [`PowerPreference::HighPerformance`].
+    pub power_preference: PowerPreference,
+    /// Requests a software adapter (e.g. lavapipe or llvmpipe) instead of a GPU.
+    pub force_fallback_adapter: bool,
+    /// Optional GPU features which have to be supported by the adapter. Defaults to none.
+    #[serde(skip)]
+    pub features: Features,
+    /// The limits which have to be supported by the adapter. Defaults to the limits of WebGL2,
+    /// which are supported almost everywhere.
+    #[serde(skip)]
+    pub limits: Limits,
+    /// Gamepad axis values below this magnitude are reported as `0`. Defaults to `0.1`.
+    pub gamepad_deadzone: f32,
+    /// Thresholds used to recognize [`Gesture`](crate::prelude::Gesture)s from touch input.
+    #[serde(skip)]
+    pub gestures: GestureConfig,
+    /// Sends the left mouse button as touch input as well, e.g. to test touch controls on desktop.
+    pub emulate_touch: bool,
 }
 
 impl Default for Config {
@@ -14,6 +129,309 @@ impl Default for Config {
             width: 1024,
             height: 768,
             resizable: false,
+            fullscreen: Fullscreen::Windowed,
+            maximized: false,
+            decorations: true,
+            min_size: None,
+            max_size: None,
+            transparent: false,
+            always_on_top: false,
+            icon: None,
+            timestep: Timestep::Variable,
+            redraw_mode: RedrawMode::Continuous,
+            present_mode: PresentMode::Fifo,
+            backend: Backend::Auto,
+            power_preference: PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            features: Features::empty(),
+            limits: Limits::downlevel_webgl2_defaults(),
+            gamepad_deadzone: 0.1,
+            gestures: GestureConfig::default(),
+            emulate_touch: false,
+        }
+    }
+}
+
+impl Config {
+    /// Loads a configuration from a `.toml` or `.ron` file.
+    pub fn from_file<P>(path: P) -> Result<Self, ErrorKind>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let format = Format::of(path)?;
+        let contents = load_string(path)?;
+        let parse_error = |e: &dyn std::fmt::Display| ErrorKind::parse(Some(path.to_owned()), e);
+
+        match format {
+            Format::Toml => toml::from_str(&contents).map_err(|e| parse_error(&e)),
+            Format::Ron => ron::from_str(&contents).map_err(|e| parse_error(&e)),
+        }
+    }
+
+    /// Saves the configuration to a `.toml` or `.ron` file.
+    pub fn save<P>(&self, path: P) -> Result<(), ErrorKind>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let contents = match Format::of(path)? {
+            Format::Toml => toml::to_string_pretty(self)
+                .map_err(|e| ErrorKind::parse(Some(path.to_owned()), e))?,
+            Format::Ron => ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+                .map_err(|e| ErrorKind::parse(Some(path.to_owned()), e))?,
+        };
+
+        fs::save_file(path, contents.as_bytes())
+    }
+
+    /// Overrides settings with command-line flags. Unknown arguments are ignored, so that applications can define their own.
+    ///
+    /// | Flag | Effect |
+    /// | --- | --- |
+    /// | `--title <title>` | Sets the window title |
+    /// | `--width <px>`, `--height <px>` | Sets the window size |
+    /// | `--fullscreen [borderless\|exclusive]`, `--windowed` | Sets [`Config::fullscreen`], borderless if no mode is given |
+    /// | `--backend <auto\|vulkan\|metal\|dx12\|dx11\|gl>` | Sets [`Config::backend`] |
+    /// | `--present-mode <fifo\|fifo-relaxed\|mailbox\|immediate>` | Sets [`Config::present_mode`] |
+    /// | `--vsync`, `--no-vsync` | Uses [`PresentMode::Fifo`] or [`PresentMode::Immediate`] |
+    /// | `--fps <fps>` | Limits the frame rate with [`RedrawMode::Capped`] |
+    /// | `--fallback-adapter` | Sets [`Config::force_fallback_adapter`] |
+    pub fn with_args<I, S>(mut self, args: I) -> Result<Self, ErrorKind>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        args::apply(&mut self, args)?;
+        Ok(self)
+    }
+
+    /// Returns the path of the settings file of the current user, e.g. `~/.config/<app_name>/settings.toml` on Linux.
+    /// Returns `None` if the platform has no such directory.
+    pub fn user_settings_path(app_name: &str) -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join(app_name).join("settings.toml"))
+    }
+
+    /// Overrides the configuration with the settings the user saved with [`Config::save_user_settings`], if there are any.
+    pub fn with_user_settings(self, app_name: &str) -> Result<Self, ErrorKind> {
+        match Self::user_settings_path(app_name) {
+            Some(path) if path.exists() => self.with_settings_file(&path),
+            _ => Ok(self),
+        }
+    }
+
+    /// Saves the settings a player would change in a settings menu: the window size, [`Config::fullscreen`],
+    /// [`Config::present_mode`] and [`Config::redraw_mode`] (e.g. a frame rate cap).
+    /// Everything else keeps coming from the configuration the application ships with.
+    pub fn save_user_settings(&self, app_name: &str) -> Result<(), ErrorKind> {
+        let path = Self::user_settings_path(app_name).ok_or_else(|| {
+            ErrorKind::io(
+                app_name,
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "No config directory for the current user",
+                ),
+            )
+        })?;
+
+        self.save_settings_file(&path)
+    }
+
+    fn with_settings_file(self, path: &Path) -> Result<Self, ErrorKind> {
+        let parse_error = |e: &dyn std::fmt::Display| ErrorKind::parse(Some(path.to_owned()), e);
+
+        // The settings are merged key by key, so that settings missing in the file keep their current value.
+        let mut settings: toml::Table =
+            toml::from_str(&load_string(path)?).map_err(|e| parse_error(&e))?;
+        settings.retain(|key, _| is_user_setting(key));
+        let mut table = toml::Table::try_from(&self).map_err(|e| parse_error(&e))?;
+        table.extend(settings);
+        let merged: Self = table.try_into().map_err(|e| parse_error(&e))?;
+
+        Ok(Self {
+            features: self.features,
+            limits: self.limits,
+            gestures: self.gestures,
+            ..merged
+        })
+    }
+
+    fn save_settings_file(&self, path: &Path) -> Result<(), ErrorKind> {
+        let parse_error = |e: &dyn std::fmt::Display| ErrorKind::parse(Some(path.to_owned()), e);
+
+        let mut settings = toml::Table::try_from(self).map_err(|e| parse_error(&e))?;
+        settings.retain(|key, _| is_user_setting(key));
+        let contents = toml::to_string_pretty(&settings).map_err(|e| parse_error(&e))?;
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|source| ErrorKind::io(dir, source))?;
+        }
+
+        fs::save_file(path, contents.as_bytes())
+    }
+}
+
+/// The file formats a [`Config`] can be stored in.
+enum Format {
+    Toml,
+    Ron,
+}
+
+impl Format {
+    fn of(path: &Path) -> Result<Self, ErrorKind> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Ok(Self::Toml),
+            Some("ron") => Ok(Self::Ron),
+            _ => Err(ErrorKind::parse(
+                Some(path.to_owned()),
+                "Unsupported format, expected a .toml or .ron file",
+            )),
         }
     }
 }
+
+fn is_user_setting(key: &str) -> bool {
+    USER_SETTINGS.contains(&key)
+}
+
+fn load_string(path: &Path) -> Result<String, ErrorKind> {
+    String::from_utf8(fs::load_file(path)?).map_err(|e| ErrorKind::parse(Some(path.to_owned()), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a path in the temporary directory which is unique to the test.
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("kaffee-{}-{name}", std::process::id()))
+    }
+
+    fn config() -> Config {
+        Config {
+            title: "Test".into(),
+            width: 1280,
+            height: 720,
+            fullscreen: Fullscreen::Exclusive,
+            min_size: Some((640, 360)),
+            timestep: Timestep::fixed(50),
+            redraw_mode: RedrawMode::Capped(144),
+            present_mode: PresentMode::Immediate,
+            backend: Backend::Vulkan,
+            ..Config::default()
+        }
+    }
+
+    fn round_trip(name: &str) -> Config {
+        let path = temp_path(name);
+        config().save(&path).unwrap();
+        let loaded = Config::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        loaded.unwrap()
+    }
+
+    #[test]
+    fn from_file_keeps_defaults() {
+        let path = temp_path("partial.toml");
+        std::fs::write(
+            &path,
+            "title = \"Partial\"\nwidth = 800\nfullscreen = \"Borderless\"\n",
+        )
+        .unwrap();
+        let config = Config::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let config = config.unwrap();
+        assert_eq!(config.title, "Partial");
+        assert_eq!((config.width, config.height), (800, 768));
+        assert_eq!(config.fullscreen, Fullscreen::Borderless);
+        assert_eq!(config.present_mode, PresentMode::Fifo);
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        assert_eq!(
+            format!("{:?}", round_trip("config.toml")),
+            format!("{:?}", config())
+        );
+    }
+
+    #[test]
+    fn ron_round_trip() {
+        assert_eq!(
+            format!("{:?}", round_trip("config.ron")),
+            format!("{:?}", config())
+        );
+    }
+
+    #[test]
+    fn user_settings_are_merged_key_by_key() {
+        let path = temp_path("merge/settings.toml");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        // Settings which are not user settings, like the title, are ignored.
+        std::fs::write(
+            &path,
+            "width = 1920\npresent_mode = \"Fifo\"\ntitle = \"Edited\"\n",
+        )
+        .unwrap();
+        let merged = config().with_settings_file(&path);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        let merged = merged.unwrap();
+        assert_eq!((merged.width, merged.height), (1920, 720));
+        assert_eq!(merged.present_mode, PresentMode::Fifo);
+        assert_eq!(merged.fullscreen, Fullscreen::Exclusive);
+        assert_eq!(merged.title, "Test");
+        assert_eq!(merged.backend, Backend::Vulkan);
+    }
+
+    #[test]
+    fn only_user_settings_are_saved() {
+        let path = temp_path("save/settings.toml");
+        config().save_settings_file(&path).unwrap();
+        let saved: toml::Table = toml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let loaded = Config::default().with_settings_file(&path);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        let mut keys: Vec<_> = saved.keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        assert_eq!(
+            keys,
+            [
+                "fullscreen",
+                "height",
+                "present_mode",
+                "redraw_mode",
+                "width"
+            ]
+        );
+
+        let loaded = loaded.unwrap();
+        assert_eq!((loaded.width, loaded.height), (1280, 720));
+        assert_eq!(loaded.redraw_mode, RedrawMode::Capped(144));
+        assert_eq!(loaded.title, Config::default().title);
+    }
+
+    #[test]
+    fn unsupported_extension() {
+        for result in [
+            Config::from_file("config.json").map(drop),
+            Config::default().save("config.yaml"),
+        ] {
+            assert!(matches!(
+                result,
+                Err(ErrorKind::ParseError { path: Some(_), .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn invalid_value() {
+        let path = temp_path("invalid.toml");
+        std::fs::write(&path, "width = \"wide\"\n").unwrap();
+        let config = Config::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(config, Err(ErrorKind::ParseError { path: Some(p), .. }) if p == path));
+    }
+}
@@ -0,0 +1,165 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    error::ErrorKind,
+    gfx::{Backend, PresentMode},
+    window::Fullscreen,
+};
+
+use super::{Config, RedrawMode};
+
+const BACKENDS: &[(&str, Backend)] = &[
+    ("auto", Backend::Auto),
+    ("vulkan", Backend::Vulkan),
+    ("metal", Backend::Metal),
+    ("dx12", Backend::Dx12),
+    ("dx11", Backend::Dx11),
+    ("gl", Backend::Gl),
+];
+
+const PRESENT_MODES: &[(&str, PresentMode)] = &[
+    ("fifo", PresentMode::Fifo),
+    ("fifo-relaxed", PresentMode::FifoRelaxed),
+    ("mailbox", PresentMode::Mailbox),
+    ("immediate", PresentMode::Immediate),
+];
+
+const FULLSCREEN: &[(&str, Fullscreen)] = &[
+    ("windowed", Fullscreen::Windowed),
+    ("borderless", Fullscreen::Borderless),
+    ("exclusive", Fullscreen::Exclusive),
+];
+
+/// Applies the flags described in [`Config::with_args`]. Values are given either as `--flag value` or `--flag=value`.
+pub(super) fn apply<I, S>(config: &mut Config, args: I) -> Result<(), ErrorKind>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let args: Vec<S> = args.into_iter().collect();
+    let mut args = args.iter().map(AsRef::as_ref).peekable();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value)),
+            None => (arg, None),
+        };
+        let mut value = || {
+            inline_value
+                .or_else(|| args.next())
+                .ok_or_else(|| ErrorKind::parse(None, format!("Missing value for {flag}")))
+        };
+
+        match flag {
+            "--title" => config.title = value()?.to_owned(),
+            "--width" => config.width = parse_number(flag, value()?)?,
+            "--height" => config.height = parse_number(flag, value()?)?,
+            "--fullscreen" => {
+                // The mode is optional, so a following argument is only taken if it names one.
+                let value = inline_value.or_else(|| {
+                    args.next_if(|next| {
+                        FULLSCREEN
+                            .iter()
+                            .any(|(name, _)| name.eq_ignore_ascii_case(next))
+                    })
+                });
+                config.fullscreen = match value {
+                    Some(value) => parse_choice(flag, value, FULLSCREEN)?,
+                    None => Fullscreen::Borderless,
+                }
+            }
+            "--windowed" => config.fullscreen = Fullscreen::Windowed,
+            "--backend" => config.backend = parse_choice(flag, value()?, BACKENDS)?,
+            "--present-mode" => {
+                config.present_mode = parse_choice(flag, value()?, PRESENT_MODES)?;
+            }
+            "--vsync" => config.present_mode = PresentMode::Fifo,
+            "--no-vsync" => config.present_mode = PresentMode::Immediate,
+            "--fps" => config.redraw_mode = RedrawMode::Capped(parse_number(flag, value()?)?),
+            "--fallback-adapter" => config.force_fallback_adapter = true,
+            // Applications may define flags of their own.
+            _ => log::debug!("Ignoring argument {arg}"),
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_number<T>(flag: &str, value: &str) -> Result<T, ErrorKind>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value
+        .parse()
+        .map_err(|e| ErrorKind::parse(None, format!("Invalid value {value:?} for {flag}: {e}")))
+}
+
+fn parse_choice<T: Copy>(flag: &str, value: &str, choices: &[(&str, T)]) -> Result<T, ErrorKind> {
+    choices
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(value))
+        .map(|&(_, choice)| choice)
+        .ok_or_else(|| {
+            let names: Vec<_> = choices.iter().map(|(name, _)| *name).collect();
+            ErrorKind::parse(
+                None,
+                format!(
+                    "Invalid value {value:?} for {flag}, expected one of {}",
+                    names.join(", ")
+                ),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_args(args: &[&str]) -> Result<Config, ErrorKind> {
+        Config::default().with_args(args)
+    }
+
+    #[test]
+    fn fullscreen_mode() {
+        let fullscreen = |args: &[&str]| with_args(args).unwrap().fullscreen;
+
+        assert_eq!(fullscreen(&["--fullscreen"]), Fullscreen::Borderless);
+        assert_eq!(
+            fullscreen(&["--fullscreen", "exclusive"]),
+            Fullscreen::Exclusive
+        );
+        assert_eq!(
+            fullscreen(&["--fullscreen=exclusive"]),
+            Fullscreen::Exclusive
+        );
+        assert_eq!(
+            fullscreen(&["--fullscreen", "Windowed"]),
+            Fullscreen::Windowed
+        );
+
+        // Other arguments are not taken as the mode.
+        let config = with_args(&["--fullscreen", "--no-vsync", "level1"]).unwrap();
+        assert_eq!(config.fullscreen, Fullscreen::Borderless);
+        assert_eq!(config.present_mode, PresentMode::Immediate);
+    }
+
+    #[test]
+    fn invalid_values() {
+        for args in [
+            &["--width", "wide"][..],
+            &["--fps=-1"],
+            &["--backend", "glide"],
+            &["--fullscreen=tiny"],
+            &["--height"],
+        ] {
+            assert!(
+                matches!(
+                    with_args(args),
+                    Err(ErrorKind::ParseError { path: None, .. })
+                ),
+                "{args:?}"
+            );
+        }
+    }
+}
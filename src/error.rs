@@ -1,17 +1,181 @@
+//! This module contains the error types returned by `kaffee`.
+
+use std::{error::Error, fmt, path::PathBuf};
+
+use crate::gfx::ShaderStage;
+
 #[derive(Debug)]
 pub enum ErrorKind {
-    IoError,
-    ImageError,
+    /// A file could not be read or written. `path` is `None` if the error is not related to a specific file.
+    IoError {
+        path: Option<PathBuf>,
+        source: std::io::Error,
+    },
+    /// An image could not be decoded or encoded.
+    ImageError {
+        path: Option<PathBuf>,
+        source: image::ImageError,
+    },
+    /// A file could not be parsed or serialized.
+    ParseError {
+        path: Option<PathBuf>,
+        message: String,
+    },
+    /// The window could not be created.
+    WindowError(winit::error::OsError),
+    /// The [`Config`](crate::prelude::Config) contains a value which cannot be used.
+    InvalidConfig(String),
     Gfx(GfxErrorKind),
 }
 
 #[derive(Debug)]
 pub enum GfxErrorKind {
-    SurfaceError,
+    /// No surface could be created for the window.
+    CreateSurfaceError(wgpu::CreateSurfaceError),
+    /// The adapter cannot present to the surface of the window.
+    IncompatibleSurface,
+    /// The next frame could not be acquired from the surface.
+    SurfaceError(wgpu::SurfaceError),
+    /// No adapter (i.e. GPU or software renderer) matching the configuration was found.
+    AdapterNotFound,
+    /// The adapter does not support all [`Features`](crate::prelude::Features) required by the configuration.
+    UnsupportedFeatures(wgpu::Features),
+    /// The adapter does not support one of the [`Limits`](crate::prelude::Limits) required by the configuration.
+    UnsupportedLimit {
+        name: &'static str,
+        requested: u64,
+        supported: u64,
+    },
+    /// The adapter failed to create a device.
+    RequestDeviceError(wgpu::RequestDeviceError),
+    /// The GPU ran out of memory.
+    OutOfMemory,
+    /// The GPU reported an error for an earlier call, e.g. because a resource was used incorrectly.
+    ValidationError(String),
+    /// A shader failed to compile.
+    ShaderError { stage: ShaderStage, message: String },
+    /// A font could not be loaded.
+    FontError(String),
+    /// Queued text could not be drawn.
+    TextError(String),
+    /// A frame could not be read back from the GPU.
+    ReadbackError(String),
+}
+
+impl ErrorKind {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        Self::IoError {
+            path: Some(path.into()),
+            source,
+        }
+    }
+
+    pub(crate) fn parse(path: Option<PathBuf>, message: impl ToString) -> Self {
+        Self::ParseError {
+            path,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IoError {
+                path: Some(path),
+                source,
+            } => write!(f, "Failed to access {}: {source}", path.display()),
+            Self::IoError { path: None, source } => write!(f, "I/O error: {source}"),
+            Self::ImageError {
+                path: Some(path),
+                source,
+            } => write!(f, "Failed to load image {}: {source}", path.display()),
+            Self::ImageError { path: None, source } => write!(f, "Failed to load image: {source}"),
+            Self::ParseError {
+                path: Some(path),
+                message,
+            } => write!(f, "Failed to parse {}: {message}", path.display()),
+            Self::ParseError {
+                path: None,
+                message,
+            } => write!(f, "Failed to parse: {message}"),
+            Self::WindowError(e) => write!(f, "Failed to create window: {e}"),
+            Self::InvalidConfig(message) => write!(f, "Invalid configuration: {message}"),
+            Self::Gfx(e) => e.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for GfxErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CreateSurfaceError(e) => write!(f, "Failed to create surface: {e}"),
+            Self::IncompatibleSurface => {
+                write!(f, "The graphics adapter cannot present to the window")
+            }
+            Self::SurfaceError(e) => write!(f, "Failed to acquire frame: {e}"),
+            Self::AdapterNotFound => write!(f, "No compatible graphics adapter was found"),
+            Self::UnsupportedFeatures(features) => {
+                write!(f, "The graphics adapter does not support {features:?}")
+            }
+            Self::UnsupportedLimit {
+                name,
+                requested,
+                supported,
+            } => write!(
+                f,
+                "The graphics adapter does not support {name} = {requested}, it supports {supported}"
+            ),
+            Self::RequestDeviceError(e) => write!(f, "Failed to request device: {e}"),
+            Self::OutOfMemory => write!(f, "The graphics device ran out of memory"),
+            Self::ValidationError(e) => write!(f, "Graphics error: {e}"),
+            Self::ShaderError { stage, message } => {
+                write!(f, "Failed to compile {stage:?} shader: {message}")
+            }
+            Self::FontError(e) => write!(f, "Failed to load font: {e}"),
+            Self::TextError(e) => write!(f, "Failed to draw text: {e}"),
+            Self::ReadbackError(e) => write!(f, "Failed to read back frame: {e}"),
+        }
+    }
+}
+
+impl Error for ErrorKind {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::IoError { source, .. } => Some(source),
+            Self::ImageError { source, .. } => Some(source),
+            Self::WindowError(e) => Some(e),
+            Self::Gfx(e) => e.source(),
+            Self::ParseError { .. } | Self::InvalidConfig(_) => None,
+        }
+    }
+}
+
+impl Error for GfxErrorKind {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::CreateSurfaceError(e) => Some(e),
+            Self::SurfaceError(e) => Some(e),
+            Self::RequestDeviceError(e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
 impl From<std::io::Error> for ErrorKind {
-    fn from(_: std::io::Error) -> Self {
-        Self::IoError
+    fn from(source: std::io::Error) -> Self {
+        Self::IoError { path: None, source }
+    }
+}
+
+impl From<image::ImageError> for ErrorKind {
+    fn from(source: image::ImageError) -> Self {
+        Self::ImageError { path: None, source }
+    }
+}
+
+impl From<GfxErrorKind> for ErrorKind {
+    fn from(e: GfxErrorKind) -> Self {
+        Self::Gfx(e)
     }
 }
@@ -8,7 +8,58 @@ pub trait EventHandler {
 
     fn input(&mut self, event: InputEvent);
 
-    fn update(&mut self, dt: f32);
+    /// Advances the simulation by `dt` seconds. The current [`InputState`](crate::prelude::InputState)
+    /// is available through [`GfxContext::input`].
+    fn update(&mut self, g: &mut GfxContext, dt: f32);
 
     fn redraw(&mut self, g: &mut GfxContext);
+
+    /// Called after the window was resized or moved to a display with a different scale factor.
+    /// `width` and `height` are given in logical pixels.
+    fn resize(&mut self, _g: &mut GfxContext, _width: f32, _height: f32) {}
+
+    /// Called when the window gained or lost keyboard focus, e.g. to pause the game.
+    fn focus_changed(&mut self, _g: &mut GfxContext, _focused: bool) {}
+
+    /// Called when the user tries to close the window. Returning `false` keeps the application running,
+    /// e.g. to ask whether unsaved changes should be saved first. The application can be closed later with [`GfxContext::quit`].
+    fn quit_requested(&mut self, _g: &mut GfxContext) -> bool {
+        true
+    }
+
+    /// Called when the application is suspended, e.g. when it is sent to the background on mobile platforms.
+    /// No frames are drawn until it is resumed.
+    fn suspended(&mut self, _g: &mut GfxContext) {}
+
+    /// Called when the application is resumed after it was suspended.
+    fn resumed(&mut self, _g: &mut GfxContext) {}
+
+    /// Called once before the application exits, e.g. to save data. This is also called if the application exits because of an error.
+    fn shutdown(&mut self, _g: &mut GfxContext) {}
+
+    /// Called after the graphics device was lost (e.g. because the driver was reset) and recreated.
+    /// Textures and shaders created before belong to the old device and have to be created again.
+    fn device_restored(&mut self, _g: &mut GfxContext) -> Result<(), ErrorKind> {
+        Ok(())
+    }
+}
+
+/// Resizes `g` to the given physical size and notifies `handler`.
+pub(crate) fn resize<H: EventHandler>(
+    handler: &mut H,
+    g: &mut GfxContext,
+    width: u32,
+    height: u32,
+    scale_factor: f64,
+) {
+    g.resize(width, height, scale_factor);
+
+    let (width, height) = (g.width(), g.height());
+    handler.resize(g, width, height);
+}
+
+/// Updates the input state of `g` and passes the event on to `handler`.
+pub(crate) fn dispatch<H: EventHandler>(handler: &mut H, g: &mut GfxContext, event: InputEvent) {
+    g.input_mut().process(&event);
+    handler.input(event);
 }
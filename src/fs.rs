@@ -1,10 +1,36 @@
 use std::{fs, path::Path};
 
+use image::RgbaImage;
+
 use crate::error::ErrorKind;
 
 pub(crate) fn load_file<P>(path: P) -> Result<Vec<u8>, ErrorKind>
 where
     P: AsRef<Path>,
 {
-    Ok(fs::read(path)?)
+    let path = path.as_ref();
+    fs::read(path).map_err(|source| ErrorKind::io(path, source))
+}
+
+pub(crate) fn save_file<P>(path: P, contents: &[u8]) -> Result<(), ErrorKind>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    fs::write(path, contents).map_err(|source| ErrorKind::io(path, source))
+}
+
+/// Loads and decodes an image file.
+pub(crate) fn load_image<P>(path: P) -> Result<RgbaImage, ErrorKind>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let bytes = load_file(path)?;
+    let image = image::load_from_memory(&bytes).map_err(|source| ErrorKind::ImageError {
+        path: Some(path.to_owned()),
+        source,
+    })?;
+
+    Ok(image.to_rgba8())
 }
@@ -1,3 +1,4 @@
+mod blend;
 mod buffer;
 mod camera;
 mod color;
@@ -5,6 +6,13 @@ mod context;
 mod texture;
 mod types;
 
+pub use blend::BlendMode;
+pub use camera::Camera2D;
 pub use color::Color;
-pub use context::{BatchExt, GfxContext, PipelineDescriptor, PipelineExt, TextExt, TextureExt};
-pub use texture::FilterMode;
+#[allow(unused_imports)]
+pub use context::PipelineDescriptor;
+pub use context::{BatchExt, GfxContext, PipelineExt, TextExt, TextureExt, WindowExt};
+pub use texture::{FilterMode, TextureRef};
+pub use types::{
+    AdapterInfo, Backend, Features, Limits, PowerPreference, PresentMode, ShaderStage,
+};
@@ -0,0 +1,52 @@
+/// Determines how drawn pixels are combined with the pixels already on screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BlendMode {
+    /// Regular transparency, using the alpha channel of the source.
+    #[default]
+    Alpha,
+    /// Like [`BlendMode::Alpha`], but for colors which were already multiplied by their alpha.
+    Premultiplied,
+    /// Adds the source to the destination, e.g. for light or particle effects.
+    Additive,
+    /// Multiplies the source with the destination, which darkens the result.
+    Multiply,
+    /// The inverse of [`BlendMode::Multiply`], which brightens the result.
+    Screen,
+    /// Overwrites the destination and ignores the alpha channel.
+    Replace,
+}
+
+impl BlendMode {
+    pub(crate) const ALL: [BlendMode; 6] = [
+        BlendMode::Alpha,
+        BlendMode::Premultiplied,
+        BlendMode::Additive,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+        BlendMode::Replace,
+    ];
+}
+
+impl From<BlendMode> for wgpu::BlendState {
+    fn from(blend_mode: BlendMode) -> Self {
+        use wgpu::{BlendComponent, BlendFactor, BlendOperation, BlendState};
+
+        let color = |src_factor, dst_factor| BlendState {
+            color: BlendComponent {
+                src_factor,
+                dst_factor,
+                operation: BlendOperation::Add,
+            },
+            alpha: BlendComponent::OVER,
+        };
+
+        match blend_mode {
+            BlendMode::Alpha => BlendState::ALPHA_BLENDING,
+            BlendMode::Premultiplied => BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+            BlendMode::Additive => color(BlendFactor::SrcAlpha, BlendFactor::One),
+            BlendMode::Multiply => color(BlendFactor::Dst, BlendFactor::Zero),
+            BlendMode::Screen => color(BlendFactor::One, BlendFactor::OneMinusSrc),
+            BlendMode::Replace => BlendState::REPLACE,
+        }
+    }
+}
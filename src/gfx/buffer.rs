@@ -8,6 +8,7 @@ use super::types::BufferUsages;
 pub struct Buffer<T: Pod> {
     buffer: wgpu::Buffer,
     cap: u64,
+    #[allow(dead_code)]
     len: u64,
     data: std::marker::PhantomData<T>,
 }
@@ -34,6 +35,7 @@ impl<T: Pod> Buffer<T> {
         &self.buffer
     }
 
+    #[allow(dead_code)]
     pub fn len(&self) -> u64 {
         self.len
     }
@@ -1,4 +1,8 @@
-use glam::{Mat4, Quat, Vec3};
+use glam::{Mat4, Quat, Vec2, Vec3};
+
+use crate::math::Rect;
+
+use super::buffer::MutableBuffer;
 
 #[rustfmt::skip]
 const OPENGL_TO_WGPU_MATRIX: Mat4 = Mat4::from_cols_array(&[
@@ -8,6 +12,107 @@ const OPENGL_TO_WGPU_MATRIX: Mat4 = Mat4::from_cols_array(&[
     0.0, 0.0, 0.5, 1.0,
 ]);
 
+/// A 2D camera which determines which part of the world is visible.
+/// All screen coordinates are given in logical pixels.
+///
+/// The default camera shows the world at its original scale, with the world origin in the top left corner of the screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera2D {
+    /// The point in world space which is displayed at [`Camera2D::origin`].
+    pub position: Vec2,
+    /// The scale of the world. Values above `1` zoom in.
+    pub zoom: f32,
+    /// The rotation of the world around [`Camera2D::position`], in radians.
+    pub rotation: f32,
+    /// The point on screen, relative to the viewport, at which [`Camera2D::position`] is displayed.
+    /// Set this to half the viewport size to center the camera.
+    pub origin: Vec2,
+    /// The area of the screen to draw to. `None` uses the whole screen.
+    pub viewport: Option<Rect>,
+}
+
+impl Default for Camera2D {
+    fn default() -> Self {
+        Self {
+            position: Vec2::ZERO,
+            zoom: 1.,
+            rotation: 0.,
+            origin: Vec2::ZERO,
+            viewport: None,
+        }
+    }
+}
+
+impl Camera2D {
+    /// Creates a camera which displays `position` in the center of a screen with the given size.
+    pub fn centered(position: Vec2, screen_width: f32, screen_height: f32) -> Self {
+        Self {
+            position,
+            origin: Vec2::new(screen_width, screen_height) / 2.,
+            ..Self::default()
+        }
+    }
+
+    /// Converts a point on screen (e.g. the mouse position) into world space.
+    pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
+        let point = point - self.viewport_offset();
+        self.view()
+            .inverse()
+            .transform_point3(point.extend(0.))
+            .truncate()
+    }
+
+    /// Converts a point in world space into a point on screen.
+    pub fn world_to_screen(&self, point: Vec2) -> Vec2 {
+        self.view().transform_point3(point.extend(0.)).truncate() + self.viewport_offset()
+    }
+
+    /// Returns the transformation from world space into the viewport.
+    fn view(&self) -> Mat4 {
+        Mat4::from_translation(self.origin.extend(0.))
+            * Mat4::from_scale_rotation_translation(
+                Vec3::new(self.zoom, self.zoom, 1.),
+                Quat::from_rotation_z(self.rotation),
+                Vec3::ZERO,
+            )
+            * Mat4::from_translation(-self.position.extend(0.))
+    }
+
+    fn viewport_offset(&self) -> Vec2 {
+        self.viewport
+            .map(|viewport| viewport.min)
+            .unwrap_or(Vec2::ZERO)
+    }
+
+    /// Returns the part of the viewport which lies on a screen of the given size,
+    /// or `None` if the viewport is empty or completely off-screen.
+    pub(super) fn visible_area(&self, screen_width: f32, screen_height: f32) -> Option<Rect> {
+        let screen = Rect::new(0., 0., screen_width, screen_height);
+        self.viewport.unwrap_or(screen).intersection(&screen)
+    }
+
+    /// Returns the combined view and projection matrix for a screen of the given size.
+    /// Only the [visible area](Camera2D::visible_area) of the viewport is projected, since drawing is limited to the screen.
+    pub(super) fn view_projection(&self, screen_width: f32, screen_height: f32) -> Mat4 {
+        let area = self
+            .visible_area(screen_width, screen_height)
+            .unwrap_or(Rect::new(0., 0., screen_width, screen_height));
+        let offset = self.viewport_offset();
+
+        let proj = Mat4::orthographic_rh_gl(
+            area.min.x - offset.x,
+            area.max.x - offset.x,
+            area.max.y - offset.y,
+            area.min.y - offset.y,
+            -1.,
+            1.,
+        );
+
+        OPENGL_TO_WGPU_MATRIX * proj * self.view()
+    }
+}
+
+/// The camera data as it is laid out in the uniform buffer.
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable, Debug)]
 pub struct Camera {
@@ -16,21 +121,150 @@ pub struct Camera {
 }
 
 impl Camera {
-    pub(super) fn new(width: f32, height: f32, x: f32, y: f32) -> Self {
-        let proj = Mat4::orthographic_rh_gl(0., width, height, 0., -1., 1.);
+    pub(super) fn new(view_projection: Mat4) -> Self {
+        Self {
+            view_projection,
+            model: Mat4::IDENTITY,
+        }
+    }
+}
 
-        let view = Mat4::from_scale_rotation_translation(
-            Vec3::new(1., 1., 1.),
-            Quat::IDENTITY,
-            Vec3::new(x, y, 0.0),
-        );
+/// A uniform buffer holding a single [`Camera`], together with its bind group.
+/// Each camera used within a frame gets its own slot.
+pub(super) struct CameraSlot {
+    buffer: MutableBuffer<Camera>,
+    bind_group: wgpu::BindGroup,
+}
 
-        let view_projection = OPENGL_TO_WGPU_MATRIX * proj * view;
-        let model = Mat4::IDENTITY;
+impl CameraSlot {
+    pub(super) fn new(device: &wgpu::Device, layout: &wgpu::BindGroupLayout) -> Self {
+        let buffer = MutableBuffer::with_capacity(device, wgpu::BufferUsages::UNIFORM, 1);
 
-        Self {
-            view_projection,
-            model,
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.handle().as_entire_binding(),
+            }],
+        });
+
+        Self { buffer, bind_group }
+    }
+
+    pub(super) fn upload(&self, queue: &wgpu::Queue, camera: Camera) {
+        self.buffer.upload(queue, &[camera]);
+    }
+
+    pub(super) fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    fn assert_near(a: Vec2, b: Vec2) {
+        assert!(a.abs_diff_eq(b, 1e-4), "{a} != {b}");
+    }
+
+    fn cameras() -> [Camera2D; 4] {
+        [
+            Camera2D::default(),
+            Camera2D {
+                position: Vec2::new(10., -20.),
+                zoom: 2.5,
+                ..Camera2D::default()
+            },
+            Camera2D {
+                rotation: 0.7,
+                origin: Vec2::new(320., 240.),
+                ..Camera2D::centered(Vec2::new(-5., 8.), 640., 480.)
+            },
+            Camera2D {
+                position: Vec2::new(3., 4.),
+                zoom: 0.5,
+                rotation: -2.,
+                origin: Vec2::new(50., 25.),
+                viewport: Some(Rect::new(100., 60., 200., 100.)),
+            },
+        ]
+    }
+
+    #[test]
+    fn round_trips() {
+        for camera in cameras() {
+            for point in [Vec2::ZERO, Vec2::new(12.5, -7.), Vec2::new(-300., 1000.)] {
+                assert_near(camera.screen_to_world(camera.world_to_screen(point)), point);
+                assert_near(camera.world_to_screen(camera.screen_to_world(point)), point);
+            }
         }
     }
+
+    #[test]
+    fn position_is_shown_at_origin() {
+        for camera in cameras() {
+            let origin = camera.origin + camera.viewport_offset();
+            assert_near(camera.world_to_screen(camera.position), origin);
+        }
+    }
+
+    #[test]
+    fn zoom_and_rotation() {
+        let camera = Camera2D {
+            zoom: 2.,
+            rotation: FRAC_PI_2,
+            ..Camera2D::default()
+        };
+
+        // The y axis points down, so a positive rotation turns the x axis towards the bottom of the screen.
+        assert_near(camera.world_to_screen(Vec2::new(1., 0.)), Vec2::new(0., 2.));
+        assert_near(camera.screen_to_world(Vec2::new(0., 2.)), Vec2::new(1., 0.));
+    }
+
+    #[test]
+    fn visible_area() {
+        let camera = |viewport| Camera2D {
+            viewport,
+            ..Camera2D::default()
+        };
+
+        assert_eq!(
+            camera(None).visible_area(640., 480.),
+            Some(Rect::new(0., 0., 640., 480.))
+        );
+        assert_eq!(
+            camera(Some(Rect::new(-10., 400., 100., 200.))).visible_area(640., 480.),
+            Some(Rect::new(0., 400., 90., 80.))
+        );
+        assert_eq!(
+            camera(Some(Rect::new(700., 0., 100., 100.))).visible_area(640., 480.),
+            None
+        );
+        assert_eq!(
+            camera(Some(Rect::new(10., 10., 0., 100.))).visible_area(640., 480.),
+            None
+        );
+    }
+
+    #[test]
+    fn clamped_projection_keeps_scale() {
+        // Only the right half of the viewport is on screen, which must not stretch the visible part.
+        let camera = Camera2D {
+            viewport: Some(Rect::new(-100., 0., 200., 100.)),
+            ..Camera2D::default()
+        };
+        let project = |point: Vec2| {
+            camera
+                .view_projection(640., 480.)
+                .project_point3(point.extend(0.))
+                .truncate()
+        };
+
+        assert_near(project(Vec2::new(100., 0.)), Vec2::new(-1., 1.));
+        assert_near(project(Vec2::new(200., 100.)), Vec2::new(1., -1.));
+    }
 }
@@ -22,12 +22,14 @@ impl Color {
     }
 }
 
+#[allow(clippy::from_over_into)]
 impl Into<[f32; 3]> for Color {
     fn into(self) -> [f32; 3] {
         [self.r, self.g, self.b]
     }
 }
 
+#[allow(clippy::from_over_into)]
 impl Into<[f32; 4]> for Color {
     fn into(self) -> [f32; 4] {
         [self.r, self.g, self.b, self.a]
@@ -35,6 +37,7 @@ impl Into<[f32; 4]> for Color {
 }
 
 #[doc(hidden)]
+#[allow(clippy::from_over_into)]
 impl Into<wgpu::Color> for Color {
     fn into(self) -> wgpu::Color {
         wgpu::Color {
@@ -1,142 +1,195 @@
 mod batch;
 mod batch_ext;
 mod buffer_ext;
+mod device;
 mod pass;
 mod pipeline_desc;
 mod pipeline_ext;
+mod target;
 mod text_ext;
 mod texture_ext;
+mod window_ext;
 
 pub use batch_ext::BatchExt;
+#[allow(unused_imports)]
 pub use buffer_ext::BufferExt;
 pub use pipeline_desc::PipelineDescriptor;
 pub use pipeline_ext::PipelineExt;
 pub use text_ext::TextExt;
 pub use texture_ext::TextureExt;
+pub use window_ext::WindowExt;
 
+use std::{collections::HashMap, sync::Arc};
+
+use image::RgbaImage;
 use wgpu_glyph::{ab_glyph, GlyphBrush, GlyphBrushBuilder};
 use winit::window::Window;
 
-use crate::{config::Config, error::ErrorKind, gfx::context::pipeline_ext::create_pipeline};
+use crate::{
+    config::{Config, RedrawMode},
+    error::{ErrorKind, GfxErrorKind},
+    gfx::context::pipeline_ext::create_pipeline,
+    input::InputState,
+};
 
-use self::{batch::Batch, pass::RenderPass};
+use self::{
+    batch::Batch,
+    device::{DeviceRequest, DeviceStatus},
+    pass::RenderPass,
+    target::RenderTarget,
+};
 
 use super::{
-    buffer::{Buffer, MutableBuffer},
-    camera::Camera,
+    camera::{Camera, Camera2D, CameraSlot},
     texture::{Texture, TextureRef},
-    types::{Pipeline, Shader, ShaderStage, Vertex},
-    Color,
+    types::{AdapterInfo, Pipeline, PresentMode, Shader, ShaderStage},
+    BlendMode, Color,
 };
 
-const MAX_QUAD_COUNT: usize = 1000;
-const DEFAULT_VERTEX_SHADER: &'static str = include_str!("../../res/shaders/default.vert.glsl");
-const DEFAULT_FRAGMENT_SHADER: &'static str = include_str!("../../res/shaders/default.frag.glsl");
+/// The number of quads the batch can hold before its buffers have to grow.
+const INITIAL_QUAD_CAPACITY: usize = 1000;
+const DEFAULT_VERTEX_SHADER: &str = include_str!("../../res/shaders/default.vert.glsl");
+const DEFAULT_FRAGMENT_SHADER: &str = include_str!("../../res/shaders/default.frag.glsl");
 
 /// Enables basic operations like drawing or shader creation.
 /// This type implements multiple extension traits such as [`TextureExt`] or [`BatchExt`] to keep the code cleaner and more readable.
 pub struct GfxContext {
-    instance: wgpu::Instance,
+    /// The instance is reused when recreating the device, since some backends do not support multiple instances at once.
+    instance: Arc<wgpu::Instance>,
     device: wgpu::Device,
     queue: wgpu::Queue,
-    surface: wgpu::Surface,
+    target: RenderTarget,
+    device_status: Arc<DeviceStatus>,
+    /// The pending request for a new device after the current one was lost.
+    device_request: Option<DeviceRequest>,
+    adapter_info: AdapterInfo,
+    present_mode: PresentMode,
+    redraw_mode: RedrawMode,
+    /// The configuration the context was created with, which is needed to recreate it after the device was lost.
+    config: Config,
+    scale_factor: f64,
     texture_format: wgpu::TextureFormat,
     clear_color: Color,
-    pipeline: Pipeline,
-    batch: Batch<MAX_QUAD_COUNT>,
+    pipelines: HashMap<BlendMode, Pipeline>,
+    blend_mode: BlendMode,
+    batch: Batch,
     staging_belt: wgpu::util::StagingBelt,
     render_passes: Vec<RenderPass>,
     default_texture: TextureRef,
     glyph_brush: GlyphBrush<()>,
-    camera: Camera,
-    camera_buffer: Buffer<Camera>,
-    camera_bind_group: wgpu::BindGroup,
+    camera: Camera2D,
+    /// The cameras used within the current frame. Each render pass refers to one of them.
+    frame_cameras: Vec<Camera2D>,
+    camera_slots: Vec<CameraSlot>,
+    camera_bind_group_layout: wgpu::BindGroupLayout,
+    alpha: f32,
+    redraw_requested: bool,
+    quit: bool,
+    /// An error of the event handler which is returned from the current frame. See [`GfxContext::fail`].
+    handler_error: Option<ErrorKind>,
+    input: InputState,
+    /// The window drawn into, or `None` when running headless. It is declared last so that it outlives the surface.
+    window: Option<Window>,
 }
 
 impl GfxContext {
-    /// Creates a new [`GfxContext`]. When creating a new app, an instance of [`GfxContext`] will be created as well.
-    pub async fn new(window: &Window, config: &Config) -> Result<Self, ErrorKind> {
-        let width = window.inner_size().width;
-        let height = window.inner_size().height;
-
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
-            dx12_shader_compiler: wgpu::Dx12Compiler::default(),
-        });
+    /// Creates a new [`GfxContext`] which draws into `window`. When creating a new app, an instance of [`GfxContext`] will be created as well.
+    ///
+    /// The context takes ownership of the window, since the surface it draws into must not outlive it.
+    /// The window is still available through [`GfxContext::window`].
+    pub async fn new(window: Window, config: &Config) -> Result<Self, ErrorKind> {
+        Self::with_window(Arc::new(create_instance(config)), window, config).await
+    }
 
-        let surface = unsafe { instance.create_surface(window) }.expect("Failed to create surface");
+    async fn with_window(
+        instance: Arc<wgpu::Instance>,
+        window: Window,
+        config: &Config,
+    ) -> Result<Self, ErrorKind> {
+        let surface = create_surface(&instance, &window)?;
+        let (adapter, device, queue) = request_device(&instance, Some(&surface), config).await?;
+        let target = surface_target(surface, &adapter, &window, config)?;
 
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                compatible_surface: Some(&surface),
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                force_fallback_adapter: false,
-            })
-            .await
-            .expect("No suitable adapter was found");
-
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: None,
-                    features: wgpu::Features::empty(),
-                    limits: wgpu::Limits::downlevel_webgl2_defaults(),
-                },
-                None,
-            )
-            .await
-            .expect("Failed to request a device");
-
-        let surface_caps = surface.get_capabilities(&adapter);
-        log::info!("{surface_caps:#?}");
-
-        let texture_format = surface_caps
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(surface_caps.formats[0]);
-
-        let surface_config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: texture_format,
-            width,
-            height,
-            present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode: wgpu::CompositeAlphaMode::Auto,
-            view_formats: vec![],
-        };
+        Self::with_target(
+            instance,
+            &adapter,
+            device,
+            queue,
+            target,
+            Some(window),
+            config,
+        )
+    }
+
+    /// Creates a new [`GfxContext`] which renders into an offscreen texture of `config.width` x `config.height` pixels instead of a window.
+    /// This works without a display server, e.g. in CI. Set [`Config::force_fallback_adapter`] to render on a software adapter.
+    /// The last frame can be retrieved with [`GfxContext::read_frame`].
+    pub async fn new_headless(config: &Config) -> Result<Self, ErrorKind> {
+        Self::headless(Arc::new(create_instance(config)), config).await
+    }
+
+    async fn headless(instance: Arc<wgpu::Instance>, config: &Config) -> Result<Self, ErrorKind> {
+        let (adapter, device, queue) = request_device(&instance, None, config).await?;
 
-        surface.configure(&device, &surface_config);
+        let target = RenderTarget::offscreen(&device, config.width as u32, config.height as u32);
 
+        Self::with_target(instance, &adapter, device, queue, target, None, config)
+    }
+
+    fn with_target(
+        instance: Arc<wgpu::Instance>,
+        adapter: &wgpu::Adapter,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        mut target: RenderTarget,
+        window: Option<Window>,
+        config: &Config,
+    ) -> Result<Self, ErrorKind> {
         use super::types::create_shader;
 
-        let vertex_shader = create_shader(&device, ShaderStage::Vertex, DEFAULT_VERTEX_SHADER);
+        let present_mode = target.set_present_mode(&device, config.present_mode);
+        if present_mode != config.present_mode {
+            log::info!(
+                "{:?} is not supported, using {present_mode:?}",
+                config.present_mode
+            );
+        }
+
+        let adapter_info = adapter.get_info();
+        log::info!(
+            "Using {} ({:?}, {:?})",
+            adapter_info.name,
+            adapter_info.backend,
+            adapter_info.device_type
+        );
+
+        let texture_format = target.format();
+
+        let device_status = DeviceStatus::watch(&device);
+
+        let vertex_shader = create_shader(&device, ShaderStage::Vertex, DEFAULT_VERTEX_SHADER)?;
 
         let fragment_shader =
-            create_shader(&device, ShaderStage::Fragment, DEFAULT_FRAGMENT_SHADER);
+            create_shader(&device, ShaderStage::Fragment, DEFAULT_FRAGMENT_SHADER)?;
 
-        let default_texture = Texture::from_bytes(
+        let default_texture = Texture::from_pixel(
             &device,
             &queue,
-            vec![255, 255, 255, 255],
+            [255, 255, 255, 255],
             crate::prelude::FilterMode::Nearest,
         )?;
 
-        let batch = Batch::new(&device);
+        let batch = Batch::with_capacity(&device, INITIAL_QUAD_CAPACITY);
 
         let default_font =
             ab_glyph::FontArc::try_from_slice(include_bytes!("../../res/fonts/KenneyMini.ttf"))
-                .expect("Failed to create default font");
+                .map_err(|e| GfxErrorKind::FontError(e.to_string()))?;
 
         let staging_belt = wgpu::util::StagingBelt::new(1024);
         let glyph_brush = GlyphBrushBuilder::using_font(default_font)
             .texture_filter_method(wgpu::FilterMode::Nearest)
             .build(&device, texture_format);
 
-        let camera = Camera::new(config.width as f32, config.height as f32, 0., 0.);
-        let camera_buffer = Buffer::from_data(&device, wgpu::BufferUsages::UNIFORM, &[camera]);
-
         let camera_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: None,
@@ -152,44 +205,59 @@ impl GfxContext {
                 }],
             });
 
-        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &camera_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: camera_buffer.handle().as_entire_binding(),
-            }],
-        });
+        let descriptor = PipelineDescriptor {
+            vertex_shader,
+            fragment_shader,
+            texture_format,
+            blend_mode: BlendMode::default(),
+        };
 
-        let pipeline = create_pipeline(
-            &device,
-            &PipelineDescriptor {
-                vertex_shader,
-                fragment_shader,
-                texture_format,
-            },
-            &default_texture,
-            &camera_bind_group_layout,
-        );
+        // A pipeline is created for every blend mode, so that the batch can switch between them.
+        let pipelines = BlendMode::ALL
+            .into_iter()
+            .map(|blend_mode| {
+                let pipeline = create_pipeline(
+                    &device,
+                    &descriptor,
+                    blend_mode,
+                    &default_texture,
+                    &camera_bind_group_layout,
+                );
+                (blend_mode, pipeline)
+            })
+            .collect();
 
         Ok(Self {
             instance,
             device,
             queue,
-            surface,
+            target,
+            device_status,
+            device_request: None,
+            adapter_info,
+            present_mode,
+            redraw_mode: config.redraw_mode,
+            config: config.clone(),
+            scale_factor: window.as_ref().map_or(1., Window::scale_factor),
             texture_format,
             clear_color: Color::BLACK,
-            pipeline,
+            pipelines,
+            blend_mode: BlendMode::default(),
             batch,
             staging_belt,
-            render_passes: vec![RenderPass {
-                texture: default_texture.clone(),
-            }],
+            render_passes: Vec::new(),
             default_texture,
             glyph_brush,
-            camera,
-            camera_buffer,
-            camera_bind_group,
+            camera: Camera2D::default(),
+            frame_cameras: vec![Camera2D::default()],
+            camera_slots: Vec::new(),
+            camera_bind_group_layout,
+            alpha: 1.,
+            redraw_requested: false,
+            quit: false,
+            handler_error: None,
+            input: InputState::default(),
+            window,
         })
     }
 
@@ -197,7 +265,508 @@ impl GfxContext {
         self.clear_color = color;
     }
 
-    pub fn create_shader(&self, stage: ShaderStage, src: &str) -> Shader {
+    /// Returns the width of the drawable area in logical pixels.
+    pub fn width(&self) -> f32 {
+        (self.target.size().0 as f64 / self.scale_factor) as f32
+    }
+
+    /// Returns the height of the drawable area in logical pixels.
+    pub fn height(&self) -> f32 {
+        (self.target.size().1 as f64 / self.scale_factor) as f32
+    }
+
+    /// Returns the ratio between physical and logical pixels.
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Reconfigures the render target and updates the projection.
+    /// `width` and `height` are given in physical pixels.
+    pub fn resize(&mut self, width: u32, height: u32, scale_factor: f64) {
+        self.scale_factor = scale_factor;
+
+        // The surface cannot be configured with a size of zero, e.g. while the window is minimized.
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        self.target.resize(&self.device, width, height);
+    }
+
+    /// Sets the camera used by all following draw calls. Text is not affected by the camera.
+    pub fn set_camera(&mut self, camera: Camera2D) {
+        self.camera = camera;
+        self.frame_cameras.push(camera);
+    }
+
+    pub fn camera(&self) -> &Camera2D {
+        &self.camera
+    }
+
+    /// Uploads the cameras used within the current frame, creating new slots where needed.
+    fn upload_cameras(&mut self) {
+        let (width, height) = (self.width(), self.height());
+
+        while self.camera_slots.len() < self.frame_cameras.len() {
+            let slot = CameraSlot::new(&self.device, &self.camera_bind_group_layout);
+            self.camera_slots.push(slot);
+        }
+
+        for (camera, slot) in self.frame_cameras.iter().zip(&self.camera_slots) {
+            slot.upload(
+                &self.queue,
+                Camera::new(camera.view_projection(width, height)),
+            );
+        }
+    }
+
+    /// Clears the render passes and cameras of the current frame.
+    fn reset_frame(&mut self) {
+        self.render_passes.clear();
+        self.frame_cameras.clear();
+        self.frame_cameras.push(self.camera);
+    }
+
+    /// Drops everything drawn within the current frame, e.g. because no frame could be acquired.
+    fn discard_frame(&mut self) {
+        self.reset_frame();
+        self.batch.clear();
+
+        // Queued text can only be removed by drawing it, so it is drawn into a scratch texture.
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.texture_format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        if let Err(why) = self.glyph_brush.draw_queued(
+            &self.device,
+            &mut self.staging_belt,
+            &mut encoder,
+            &view,
+            1,
+            1,
+        ) {
+            log::warn!("Failed to discard queued text: {why}");
+        }
+
+        self.staging_belt.finish();
+        self.queue.submit(Some(encoder.finish()));
+        self.staging_belt.recall();
+    }
+
+    /// Returns `true` if the device was lost, e.g. because the driver was reset.
+    /// The context can be restored with [`GfxContext::recreate`].
+    pub(crate) fn is_device_lost(&self) -> bool {
+        self.device_status.is_lost()
+    }
+
+    /// Recreates the device and all resources owned by the context after the device was lost.
+    /// The camera, blend mode, clear color and input state are preserved.
+    ///
+    /// The new device is requested without blocking. Returns `Ok(false)` while the request is still pending,
+    /// in which case this has to be called again, e.g. with the next frame. If recreating fails, the context
+    /// stays lost and keeps its window, so that it can be tried again.
+    pub(crate) fn recreate(&mut self) -> Result<bool, ErrorKind> {
+        let request = match &mut self.device_request {
+            Some(request) => request,
+            None => {
+                log::warn!("The graphics device was lost, recreating it");
+                let request = self.request_device()?;
+                self.device_request.insert(request)
+            }
+        };
+
+        let Some(result) = request.poll() else {
+            return Ok(false);
+        };
+        self.device_request = None;
+        let (surface, (adapter, device, queue)) = result?;
+
+        let target = match (surface, &self.window) {
+            (Some(surface), Some(window)) => {
+                surface_target(surface, &adapter, window, &self.config)?
+            }
+            // Headless contexts keep their size.
+            _ => {
+                let (width, height) = self.target.size();
+                RenderTarget::offscreen(&device, width, height)
+            }
+        };
+
+        let instance = Arc::clone(&self.instance);
+        let mut g = Self::with_target(
+            instance,
+            &adapter,
+            device,
+            queue,
+            target,
+            None,
+            &self.config,
+        )?;
+
+        g.config = std::mem::take(&mut self.config);
+        g.clear_color = self.clear_color;
+        g.blend_mode = self.blend_mode;
+        g.camera = self.camera;
+        g.frame_cameras = vec![self.camera];
+        g.alpha = self.alpha;
+        g.redraw_mode = self.redraw_mode;
+        g.redraw_requested = true;
+        g.quit = self.quit;
+        g.handler_error = self.handler_error.take();
+        g.input = std::mem::take(&mut self.input);
+        g.scale_factor = self.scale_factor;
+        g.window = self.window.take();
+
+        *self = g;
+        Ok(true)
+    }
+
+    /// Starts requesting a new device, together with a new surface for the window.
+    fn request_device(&mut self) -> Result<DeviceRequest, ErrorKind> {
+        let surface = match &self.window {
+            Some(window) => {
+                // The old surface has to be released before a new one can be created for the same window.
+                self.target = RenderTarget::offscreen(&self.device, 1, 1);
+                Some(create_surface(&self.instance, window)?)
+            }
+            None => None,
+        };
+
+        Ok(DeviceRequest::new(
+            Arc::clone(&self.instance),
+            surface,
+            self.config.clone(),
+        ))
+    }
+
+    /// Reads back the last frame rendered by a context created with [`GfxContext::new_headless`].
+    pub fn read_frame(&self) -> Result<RgbaImage, ErrorKind> {
+        self.target.read(&self.device, &self.queue)
+    }
+
+    /// Returns how far the current frame lies between the last and the next fixed update, in the range `0..1`.
+    /// This can be used to interpolate positions in `redraw`. When using [`Timestep::Variable`](crate::prelude::Timestep::Variable), this is always `1`.
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    pub(crate) fn set_alpha(&mut self, alpha: f32) {
+        self.alpha = alpha;
+    }
+
+    /// Returns the present mode in use, which differs from the requested one if that is not supported.
+    pub fn present_mode(&self) -> PresentMode {
+        self.present_mode
+    }
+
+    /// Changes how frames are presented, e.g. to turn vsync on or off. If `present_mode` is not supported,
+    /// the closest supported mode is used instead. Returns the mode in use.
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) -> PresentMode {
+        self.present_mode = self.target.set_present_mode(&self.device, present_mode);
+        // The requested mode is kept, so that it is used again if the device has to be recreated.
+        self.config.present_mode = present_mode;
+        self.present_mode
+    }
+
+    pub fn redraw_mode(&self) -> RedrawMode {
+        self.redraw_mode
+    }
+
+    /// Changes when new frames are drawn. [`RedrawMode::Capped`] limits the frame rate in software,
+    /// e.g. while vsync is disabled with [`PresentMode::Immediate`].
+    pub fn set_redraw_mode(&mut self, redraw_mode: RedrawMode) {
+        self.redraw_mode = redraw_mode;
+    }
+
+    /// Requests a new frame to be drawn. This is only necessary when using [`RedrawMode::Reactive`](crate::prelude::RedrawMode::Reactive),
+    /// e.g. to keep an animation running without user input.
+    pub fn request_redraw(&mut self) {
+        self.redraw_requested = true;
+    }
+
+    pub(crate) fn redraw_requested(&self) -> bool {
+        self.redraw_requested
+    }
+
+    pub(crate) fn take_redraw_request(&mut self) -> bool {
+        std::mem::take(&mut self.redraw_requested)
+    }
+
+    /// Exits the application after the current event was handled. Unlike closing the window,
+    /// this does not call [`EventHandler::quit_requested`](crate::prelude::EventHandler::quit_requested).
+    pub fn quit(&mut self) {
+        self.quit = true;
+    }
+
+    /// Stops the application with `error` at the end of the current frame. This is used by handlers which
+    /// cannot return the error themselves, e.g. when a [`Scene`](crate::prelude::Scene) fails to initialize during `update`.
+    /// Only the first error is kept.
+    pub(crate) fn fail(&mut self, error: ErrorKind) {
+        self.handler_error.get_or_insert(error);
+    }
+
+    pub(crate) fn take_handler_error(&mut self) -> Option<ErrorKind> {
+        self.handler_error.take()
+    }
+
+    pub(crate) fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Returns the current state of the keyboard and mouse.
+    pub fn input(&self) -> &InputState {
+        &self.input
+    }
+
+    /// Returns the state of the keyboard and mouse mutably, e.g. to change its [`Bindings`](crate::prelude::Bindings).
+    pub fn input_mut(&mut self) -> &mut InputState {
+        &mut self.input
+    }
+
+    /// Returns the window the context draws into, or `None` if it was created with [`GfxContext::new_headless`].
+    pub fn window(&self) -> Option<&Window> {
+        self.window.as_ref()
+    }
+
+    /// Returns information about the adapter used for rendering, e.g. the name of the GPU and its driver.
+    pub fn adapter_info(&self) -> &AdapterInfo {
+        &self.adapter_info
+    }
+
+    /// Compiles a GLSL shader, returning [`GfxErrorKind::ShaderError`] if it is invalid.
+    pub fn create_shader(&self, stage: ShaderStage, src: &str) -> Result<Shader, ErrorKind> {
         super::types::create_shader(&self.device, stage, src)
     }
 }
+
+fn create_instance(config: &Config) -> wgpu::Instance {
+    wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: config.backend.into(),
+        dx12_shader_compiler: wgpu::Dx12Compiler::default(),
+    })
+}
+
+fn create_surface(instance: &wgpu::Instance, window: &Window) -> Result<wgpu::Surface, ErrorKind> {
+    // The context owns the window and drops the surface first, so the surface never outlives it.
+    unsafe { instance.create_surface(window) }
+        .map_err(|e| GfxErrorKind::CreateSurfaceError(e).into())
+}
+
+/// Creates a target which presents to `surface`, using the size of `window`.
+/// The surface is configured once the present mode was chosen.
+fn surface_target(
+    surface: wgpu::Surface,
+    adapter: &wgpu::Adapter,
+    window: &Window,
+    config: &Config,
+) -> Result<RenderTarget, ErrorKind> {
+    let width = window.inner_size().width;
+    let height = window.inner_size().height;
+
+    let surface_caps = surface.get_capabilities(adapter);
+    log::debug!(
+        "Surface supports {:?}, {:?}",
+        surface_caps.formats,
+        surface_caps.present_modes
+    );
+
+    let texture_format = choose_format(&surface_caps.formats)?;
+
+    // Transparent windows need an alpha mode which blends the frame with whatever is behind the window.
+    let alpha_mode = surface_caps
+        .alpha_modes
+        .iter()
+        .copied()
+        .find(|mode| {
+            config.transparent
+                && matches!(
+                    mode,
+                    wgpu::CompositeAlphaMode::PreMultiplied
+                        | wgpu::CompositeAlphaMode::PostMultiplied
+                )
+        })
+        .unwrap_or(wgpu::CompositeAlphaMode::Auto);
+
+    let surface_config = wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        format: texture_format,
+        width,
+        height,
+        present_mode: wgpu::PresentMode::Fifo,
+        alpha_mode,
+        view_formats: vec![],
+    };
+
+    Ok(RenderTarget::Surface {
+        surface,
+        config: surface_config,
+        present_modes: surface_caps.present_modes,
+    })
+}
+
+/// Prefers an sRGB format, so that colors are blended in linear space.
+/// Fails if the adapter cannot present to the surface at all.
+fn choose_format(formats: &[wgpu::TextureFormat]) -> Result<wgpu::TextureFormat, GfxErrorKind> {
+    formats
+        .iter()
+        .copied()
+        .find(|f| f.is_srgb())
+        .or(formats.first().copied())
+        .ok_or(GfxErrorKind::IncompatibleSurface)
+}
+
+async fn request_device(
+    instance: &wgpu::Instance,
+    compatible_surface: Option<&wgpu::Surface>,
+    config: &Config,
+) -> Result<(wgpu::Adapter, wgpu::Device, wgpu::Queue), ErrorKind> {
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            compatible_surface,
+            power_preference: config.power_preference.into(),
+            force_fallback_adapter: config.force_fallback_adapter,
+        })
+        .await
+        .ok_or(GfxErrorKind::AdapterNotFound)?;
+
+    let missing_features = config.features - adapter.features();
+    if !missing_features.is_empty() {
+        return Err(GfxErrorKind::UnsupportedFeatures(missing_features).into());
+    }
+
+    let mut unsupported_limit = None;
+    config.limits.check_limits_with_fail_fn(
+        &adapter.limits(),
+        true,
+        |name, requested, supported| {
+            unsupported_limit = Some(GfxErrorKind::UnsupportedLimit {
+                name,
+                requested,
+                supported,
+            });
+        },
+    );
+    if let Some(e) = unsupported_limit {
+        return Err(e.into());
+    }
+
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                features: config.features,
+                limits: config.limits.clone(),
+            },
+            None,
+        )
+        .await
+        .map_err(GfxErrorKind::RequestDeviceError)?;
+
+    Ok((adapter, device, queue))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{gfx::BatchExt, testing::with_headless};
+
+    use super::*;
+
+    #[test]
+    fn prefers_srgb_formats() {
+        use wgpu::TextureFormat::{Bgra8Unorm, Bgra8UnormSrgb};
+
+        assert_eq!(
+            choose_format(&[Bgra8Unorm, Bgra8UnormSrgb]).unwrap(),
+            Bgra8UnormSrgb
+        );
+        assert_eq!(choose_format(&[Bgra8Unorm]).unwrap(), Bgra8Unorm);
+        assert!(matches!(
+            choose_format(&[]),
+            Err(GfxErrorKind::IncompatibleSurface)
+        ));
+    }
+
+    #[test]
+    fn uncaptured_errors_are_returned_from_the_next_frame() {
+        with_headless(|g| {
+            // Buffers cannot be mapped for reading and writing at once.
+            g.device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: 16,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::MAP_WRITE,
+                mapped_at_creation: false,
+            });
+
+            g.clear_color(Color::BLACK);
+            g.draw_rectangle(0., 0., 64., 64., Color::WHITE);
+
+            assert!(matches!(
+                g.end_frame(),
+                Err(ErrorKind::Gfx(GfxErrorKind::ValidationError(_)))
+            ));
+
+            // Nothing drawn before the error ends up in the next frame.
+            assert!(g.end_frame().is_ok());
+            let frame = g.read_frame().unwrap();
+            assert!(frame.pixels().all(|pixel| pixel.0 == [0, 0, 0, 255]));
+        });
+    }
+
+    #[test]
+    fn recreate_preserves_state() {
+        with_headless(|g| {
+            let camera = Camera2D {
+                zoom: 2.,
+                ..Camera2D::default()
+            };
+            g.set_camera(camera);
+            g.clear_color(Color::WHITE);
+
+            g.device_status.set_lost();
+            assert!(g.is_device_lost());
+            assert!(g.recreate().unwrap());
+
+            assert!(!g.is_device_lost());
+            assert!(g.redraw_requested());
+            assert_eq!(*g.camera(), camera);
+
+            g.end_frame().unwrap();
+            let frame = g.read_frame().unwrap();
+            assert_eq!(frame.dimensions(), (64, 64));
+            assert_eq!(frame.get_pixel(0, 0).0, [255; 4]);
+        });
+    }
+
+    #[test]
+    fn failed_recreate_can_be_retried() {
+        with_headless(|g| {
+            g.device_status.set_lost();
+            g.config.features = wgpu::Features::all();
+
+            assert!(matches!(
+                g.recreate(),
+                Err(ErrorKind::Gfx(GfxErrorKind::UnsupportedFeatures(_)))
+            ));
+            assert!(g.is_device_lost());
+
+            g.config.features = wgpu::Features::empty();
+            assert!(g.recreate().unwrap());
+            assert!(!g.is_device_lost());
+        });
+    }
+}
@@ -1,5 +1,3 @@
-use std::ops::{Index, IndexMut};
-
 use crate::gfx::{
     buffer::{Buffer, MutableBuffer},
     types::{BufferUsages, Vertex},
@@ -7,84 +5,76 @@ use crate::gfx::{
 
 use super::buffer_ext::{create_buffer, create_buffer_mut};
 
-pub(super) struct Batch<const QUAD_COUNT: usize> {
+/// Collects the quads of a frame. The GPU buffers grow whenever more quads are submitted than fit into them.
+pub(super) struct Batch {
     vertices: Vec<Vertex>,
-    vertices_off: usize,
-    index_buffer: Buffer<u16>,
+    index_buffer: Buffer<u32>,
     vertex_buffer: MutableBuffer<Vertex>,
 }
 
-impl<const QUAD_COUNT: usize> Batch<QUAD_COUNT> {
-    const VERTEX_COUNT: usize = QUAD_COUNT * 4;
-    const INDEX_COUNT: usize = QUAD_COUNT * 6;
-
-    pub fn new(device: &wgpu::Device) -> Self {
-        let mut vertices = Vec::with_capacity(Self::VERTEX_COUNT);
-        unsafe {
-            vertices.set_len(Self::VERTEX_COUNT);
-        }
-
-        let mut indices = Vec::with_capacity(Self::INDEX_COUNT);
-        unsafe {
-            indices.set_len(Self::INDEX_COUNT as usize);
-        }
-
-        let mut offset = 0;
-
-        for i in (0..Self::INDEX_COUNT as usize).step_by(6) {
-            indices[i] = 0 + offset;
-            indices[i + 1] = 1 + offset;
-            indices[i + 2] = 2 + offset;
-            indices[i + 3] = 2 + offset;
-            indices[i + 4] = 3 + offset;
-            indices[i + 5] = 0 + offset;
-            offset += 4;
-        }
-
-        let index_buffer = create_buffer(&device, BufferUsages::INDEX, &indices);
-        let vertex_buffer =
-            create_buffer_mut(&device, BufferUsages::VERTEX, Self::VERTEX_COUNT as u64);
-
+impl Batch {
+    pub fn with_capacity(device: &wgpu::Device, quad_count: usize) -> Self {
         Self {
-            vertices,
-            vertices_off: 0,
-            index_buffer,
-            vertex_buffer,
+            vertices: Vec::with_capacity(quad_count * 4),
+            index_buffer: create_index_buffer(device, quad_count),
+            vertex_buffer: create_buffer_mut(device, BufferUsages::VERTEX, quad_count as u64 * 4),
         }
     }
 
-    pub fn offset(&self) -> usize {
-        self.vertices_off
+    /// Returns the number of quads submitted since the last flush.
+    pub fn quad_count(&self) -> usize {
+        self.vertices.len() / 4
     }
 
-    pub fn set_offset(&mut self, offset: usize) {
-        self.vertices_off = offset;
+    pub fn push_quad(&mut self, quad: [Vertex; 4]) {
+        self.vertices.extend_from_slice(&quad);
     }
 
     pub fn vertex_buffer(&self) -> &MutableBuffer<Vertex> {
         &self.vertex_buffer
     }
 
-    pub fn index_buffer(&self) -> &Buffer<u16> {
+    pub fn index_buffer(&self) -> &Buffer<u32> {
         &self.index_buffer
     }
 
-    pub fn flush(&mut self, queue: &wgpu::Queue) {
-        self.vertex_buffer.upload(queue, &self.vertices);
-        self.vertices_off = 0;
+    /// Drops the submitted quads without uploading them.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
     }
-}
 
-impl<const QUAD_COUNT: usize> Index<usize> for Batch<QUAD_COUNT> {
-    type Output = Vertex;
+    /// Uploads the submitted quads, growing the buffers if necessary, and starts a new batch.
+    pub fn flush(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let vertex_count = self.vertices.len() as u64;
+
+        if vertex_count > self.vertex_buffer.cap() {
+            let quad_count = self.quad_count().next_power_of_two();
+            log::debug!("Growing batch to {quad_count} quads");
 
-    fn index(&self, index: usize) -> &Self::Output {
-        &self.vertices[index]
+            self.index_buffer = create_index_buffer(device, quad_count);
+            self.vertex_buffer =
+                create_buffer_mut(device, BufferUsages::VERTEX, quad_count as u64 * 4);
+        }
+
+        self.vertex_buffer.upload(queue, &self.vertices);
+        self.vertices.clear();
     }
 }
 
-impl<const QUAD_COUNT: usize> IndexMut<usize> for Batch<QUAD_COUNT> {
-    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        &mut self.vertices[index]
-    }
+fn create_index_buffer(device: &wgpu::Device, quad_count: usize) -> Buffer<u32> {
+    let indices: Vec<u32> = (0..quad_count as u32)
+        .flat_map(|quad| {
+            let offset = quad * 4;
+            [
+                offset,
+                offset + 1,
+                offset + 2,
+                offset + 2,
+                offset + 3,
+                offset,
+            ]
+        })
+        .collect();
+
+    create_buffer(device, BufferUsages::INDEX, &indices)
 }
@@ -1,6 +1,10 @@
-use crate::gfx::{texture::Texture, types::Vertex, Color};
+use crate::{
+    error::{ErrorKind, GfxErrorKind},
+    gfx::{texture::TextureRef, types::Vertex, BlendMode, Color},
+    math::Rect,
+};
 
-use super::GfxContext;
+use super::{pass::RenderPass, GfxContext};
 
 /// Adds support for basic drawing operations.
 pub trait BatchExt {
@@ -8,56 +12,127 @@ pub trait BatchExt {
 
     fn draw_quad(&mut self, x: f32, y: f32, w: f32, color: Color);
 
-    fn draw_texture(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color, texture: Texture);
+    /// Draws `texture` stretched over the given rectangle. The texture is tinted with `color`.
+    fn draw_texture(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color, texture: &TextureRef);
 
-    /// Ends the current frame. This does not have to be called manually.
-    fn end_frame(&mut self);
+    /// Sets the [`BlendMode`] used by all following draw calls, until it is changed again.
+    fn set_blend_mode(&mut self, blend_mode: BlendMode);
+
+    /// Ends the current frame and presents it. This does not have to be called manually.
+    fn end_frame(&mut self) -> Result<(), ErrorKind>;
 }
 
-impl BatchExt for GfxContext {
-    fn draw_rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
-        let offset = self.batch.offset();
+/// Appends a quad to the batch. Quads without a texture use the default (white) texture.
+fn push_quad(
+    g: &mut GfxContext,
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+    color: Color,
+    texture: Option<&TextureRef>,
+) {
+    let texture = texture.unwrap_or(&g.default_texture);
 
-        self.batch[offset] = Vertex {
+    // Every quad consists of 6 indices. A new pass is started whenever the texture, blend mode or camera changes.
+    let first_index = (g.batch.quad_count() * 6) as u32;
+    let camera = g.frame_cameras.len() - 1;
+
+    g.batch.push_quad([
+        Vertex {
             position: [x + w, y + h, 0.0],
             color: color.into(),
             tex_coords: [1., 1.],
-        };
-        self.batch[offset + 1] = Vertex {
+        },
+        Vertex {
             position: [x + w, y, 0.0],
             color: color.into(),
             tex_coords: [1., 0.],
-        };
-        self.batch[offset + 2] = Vertex {
+        },
+        Vertex {
             position: [x, y, 0.0],
             color: color.into(),
             tex_coords: [0., 0.],
-        };
-        self.batch[offset + 3] = Vertex {
+        },
+        Vertex {
             position: [x, y + h, 0.0],
             color: color.into(),
             tex_coords: [0., 1.],
-        };
+        },
+    ]);
 
-        self.batch.set_offset(offset + 4);
+    match g.render_passes.last_mut() {
+        Some(pass)
+            if pass.texture == *texture
+                && pass.blend_mode == g.blend_mode
+                && pass.camera == camera =>
+        {
+            pass.index_count += 6
+        }
+        _ => g.render_passes.push(RenderPass {
+            texture: texture.clone(),
+            blend_mode: g.blend_mode,
+            camera,
+            first_index,
+            index_count: 6,
+        }),
+    }
+}
+
+impl BatchExt for GfxContext {
+    fn draw_rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
+        push_quad(self, x, y, w, h, color, None);
     }
 
     fn draw_quad(&mut self, x: f32, y: f32, w: f32, color: Color) {
         self.draw_rectangle(x, y, w, w, color)
     }
 
-    fn draw_texture(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color, texture: Texture) {}
+    fn draw_texture(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color, texture: &TextureRef) {
+        push_quad(self, x, y, w, h, color, Some(texture));
+    }
+
+    fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
 
-    fn end_frame(&mut self) {
-        self.batch.flush(&self.queue);
+    fn end_frame(&mut self) -> Result<(), ErrorKind> {
+        if let Some(error) = self.device_status.take_error() {
+            self.discard_frame();
+            return Err(error.into());
+        }
 
-        let output = self
-            .surface
-            .get_current_texture()
-            .expect("Failed to retrieve current texture");
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        self.batch.flush(&self.device, &self.queue);
+        self.upload_cameras();
+
+        let acquired = if self.device_status.is_lost() {
+            Ok(None)
+        } else {
+            self.target.acquire(&self.device)
+        };
+
+        let acquired = match acquired {
+            Ok(acquired) => acquired,
+            // The surface is still lost after reconfiguring it, which means that the device is gone as well.
+            Err(wgpu::SurfaceError::Lost) => {
+                self.device_status.set_lost();
+                None
+            }
+            Err(e) => {
+                self.discard_frame();
+                return Err(GfxErrorKind::SurfaceError(e).into());
+            }
+        };
+
+        // Skipped frames are redrawn as soon as possible, which also gives a lost device the chance to be recreated.
+        let Some((frame, view)) = acquired else {
+            log::debug!("Skipping frame");
+            self.discard_frame();
+            self.request_redraw();
+            return Ok(());
+        };
+
+        let (width, height) = self.target.size();
 
         let mut encoder = self
             .device
@@ -77,31 +152,124 @@ impl BatchExt for GfxContext {
                 depth_stencil_attachment: None,
             });
 
-            rpass.set_pipeline(&self.pipeline);
             rpass.set_vertex_buffer(0, self.batch.vertex_buffer().handle().slice(..));
-            rpass.set_bind_group(0, &self.camera_bind_group, &[]);
-            rpass.set_bind_group(1, self.default_texture.bind_group(), &[]);
             rpass.set_index_buffer(
                 self.batch.index_buffer().handle().slice(..),
-                wgpu::IndexFormat::Uint16,
+                wgpu::IndexFormat::Uint32,
             );
-            rpass.draw_indexed(0..self.batch.index_buffer().len() as u32, 0, 0..1);
+
+            let scale_factor = self.scale_factor as f32;
+            let screen = Rect::new(0., 0., width as f32, height as f32);
+
+            for pass in &self.render_passes {
+                // Viewports outside of the target fail validation, so they are clipped and empty ones are skipped.
+                let viewport = self.frame_cameras[pass.camera]
+                    .visible_area(self.width(), self.height())
+                    .and_then(|area| {
+                        Rect {
+                            min: area.min * scale_factor,
+                            max: area.max * scale_factor,
+                        }
+                        .intersection(&screen)
+                    });
+                let Some(viewport) = viewport else {
+                    continue;
+                };
+
+                rpass.set_viewport(
+                    viewport.min.x,
+                    viewport.min.y,
+                    viewport.width(),
+                    viewport.height(),
+                    0.,
+                    1.,
+                );
+                rpass.set_pipeline(&self.pipelines[&pass.blend_mode]);
+                rpass.set_bind_group(0, self.camera_slots[pass.camera].bind_group(), &[]);
+                rpass.set_bind_group(1, pass.texture.bind_group(), &[]);
+                rpass.draw_indexed(pass.indices(), 0, 0..1);
+            }
         }
 
+        self.reset_frame();
+
         self.glyph_brush
             .draw_queued(
                 &self.device,
                 &mut self.staging_belt,
                 &mut encoder,
                 &view,
-                1024,
-                768,
+                width,
+                height,
             )
-            .expect("Failed to draw text");
+            .map_err(GfxErrorKind::TextError)?;
 
         self.staging_belt.finish();
         self.queue.submit(Some(encoder.finish()));
-        output.present();
+        frame.present();
         self.staging_belt.recall();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        gfx::{texture::Texture, FilterMode},
+        testing::with_headless,
+    };
+
+    use super::*;
+
+    #[test]
+    fn passes_break_on_texture_and_blend_mode_changes() {
+        with_headless(|g| {
+            let texture =
+                |pixel| Texture::from_pixel(&g.device, &g.queue, pixel, FilterMode::Nearest);
+            let a = texture([255, 0, 0, 255]).unwrap();
+            let b = texture([0, 0, 255, 255]).unwrap();
+
+            g.draw_texture(0., 0., 8., 8., Color::WHITE, &a);
+            g.draw_texture(8., 0., 8., 8., Color::WHITE, &a);
+            g.draw_texture(16., 0., 8., 8., Color::WHITE, &b);
+            g.draw_texture(24., 0., 8., 8., Color::WHITE, &a);
+            g.draw_rectangle(32., 0., 8., 8., Color::WHITE);
+            g.set_blend_mode(BlendMode::Additive);
+            g.draw_rectangle(40., 0., 8., 8., Color::WHITE);
+
+            let default = g.default_texture.clone();
+            let passes: Vec<_> = g
+                .render_passes
+                .iter()
+                .map(|pass| (pass.indices(), pass.blend_mode))
+                .collect();
+            assert_eq!(
+                passes,
+                [
+                    (0..12, BlendMode::Alpha),
+                    (12..18, BlendMode::Alpha),
+                    (18..24, BlendMode::Alpha),
+                    (24..30, BlendMode::Alpha),
+                    (30..36, BlendMode::Additive),
+                ]
+            );
+
+            let name = |texture: &TextureRef| {
+                [(&a, "a"), (&b, "b"), (&default, "default")]
+                    .into_iter()
+                    .find(|(t, _)| *t == texture)
+                    .map_or("unknown", |(_, name)| name)
+            };
+            let textures: Vec<_> = g
+                .render_passes
+                .iter()
+                .map(|pass| name(&pass.texture))
+                .collect();
+            assert_eq!(textures, ["a", "b", "a", "default", "default"]);
+
+            g.end_frame().unwrap();
+            assert!(g.render_passes.is_empty());
+        });
     }
 }
@@ -7,6 +7,7 @@ use crate::gfx::{
 
 use super::GfxContext;
 
+#[allow(dead_code)]
 pub trait BufferExt {
     fn create_buffer<T: Pod>(&self, usage: BufferUsages, data: &[T]) -> Buffer<T>;
 
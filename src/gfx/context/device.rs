@@ -0,0 +1,181 @@
+use std::{
+    error::Error,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+};
+
+use crate::error::{ErrorKind, GfxErrorKind};
+
+/// Tracks errors which wgpu reports asynchronously instead of returning them from a call.
+#[derive(Debug, Default)]
+pub(super) struct DeviceStatus {
+    lost: AtomicBool,
+    /// The first error reported since it was last taken. Later errors are often caused by the first one, so they are dropped.
+    error: Mutex<Option<GfxErrorKind>>,
+}
+
+impl DeviceStatus {
+    /// Installs an error handler on `device` which records device loss and all other errors,
+    /// so that they can be returned from the next frame instead of panicking like the default handler.
+    pub fn watch(device: &wgpu::Device) -> Arc<Self> {
+        let status = Arc::new(Self::default());
+
+        let handler = Arc::clone(&status);
+        device.on_uncaptured_error(Box::new(move |error| {
+            // Once the device is lost, every call fails, so the follow-up errors are ignored.
+            if handler.is_lost() {
+                return;
+            }
+
+            if caused_by_device_loss(&error) {
+                handler.set_lost();
+                return;
+            }
+
+            let error = match error {
+                wgpu::Error::OutOfMemory { .. } => GfxErrorKind::OutOfMemory,
+                wgpu::Error::Validation { description, .. } => {
+                    GfxErrorKind::ValidationError(description)
+                }
+            };
+            handler.record(error);
+        }));
+
+        status
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Relaxed)
+    }
+
+    pub fn set_lost(&self) {
+        self.lost.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns the first error reported since the last call, if any.
+    pub fn take_error(&self) -> Option<GfxErrorKind> {
+        self.error.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+
+    fn record(&self, error: GfxErrorKind) {
+        self.error
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_or_insert(error);
+    }
+}
+
+/// Returns `true` if `error` was caused by the device being lost.
+///
+/// wgpu-core embeds its `DeviceError` transparently into the error of each operation, which hides it from the
+/// source chain. Since a transparent error has the same message as the one it wraps, the messages are compared as well.
+#[cfg(not(target_arch = "wasm32"))]
+fn caused_by_device_loss(error: &(dyn Error + 'static)) -> bool {
+    use wgpu_core::device::DeviceError;
+
+    let lost = DeviceError::Lost.to_string();
+    std::iter::successors(Some(error), |&error| error.source()).any(|error| {
+        matches!(error.downcast_ref(), Some(DeviceError::Lost)) || error.to_string() == lost
+    })
+}
+
+/// Browsers report a lost device through the surface, which is handled when acquiring a frame.
+#[cfg(target_arch = "wasm32")]
+fn caused_by_device_loss(_: &(dyn Error + 'static)) -> bool {
+    false
+}
+
+/// A new device, together with the surface it was requested for.
+pub(super) type NewDevice = (
+    Option<wgpu::Surface>,
+    (wgpu::Adapter, wgpu::Device, wgpu::Queue),
+);
+
+/// The future of a [`DeviceRequest`]. It is `Send` wherever wgpu's types are, so that the context stays `Send` as well.
+#[cfg(not(target_arch = "wasm32"))]
+type DeviceFuture = Pin<Box<dyn Future<Output = Result<NewDevice, ErrorKind>> + Send>>;
+#[cfg(target_arch = "wasm32")]
+type DeviceFuture = Pin<Box<dyn Future<Output = Result<NewDevice, ErrorKind>>>>;
+
+/// A request for a device which replaces a lost one. It is polled once per frame instead of blocking the event loop,
+/// since the device is not available right away on every platform.
+pub(super) struct DeviceRequest {
+    future: DeviceFuture,
+}
+
+impl DeviceRequest {
+    /// Requests a device which is compatible with `surface`, if there is one. The surface is handed back together with the device.
+    pub fn new(
+        instance: Arc<wgpu::Instance>,
+        surface: Option<wgpu::Surface>,
+        config: crate::config::Config,
+    ) -> Self {
+        Self {
+            future: Box::pin(async move {
+                let parts = super::request_device(&instance, surface.as_ref(), &config).await?;
+                Ok((surface, parts))
+            }),
+        }
+    }
+
+    /// Returns the result once the request completed.
+    pub fn poll(&mut self) -> Option<Result<NewDevice, ErrorKind>> {
+        // Nothing is woken up, since the request is polled again with the next frame anyway.
+        match self
+            .future
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+        {
+            Poll::Ready(result) => Some(result),
+            Poll::Pending => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu_core::{device::DeviceError, error::ContextError, resource::CreateBufferError};
+
+    use super::*;
+
+    fn validation_error(cause: DeviceError) -> wgpu::Error {
+        let error = ContextError {
+            string: "Device::create_buffer",
+            cause: Box::new(CreateBufferError::Device(cause)),
+            label: String::new(),
+            label_key: "",
+        };
+
+        wgpu::Error::Validation {
+            description: error.to_string(),
+            source: Box::new(error),
+        }
+    }
+
+    #[test]
+    fn detects_device_loss() {
+        assert!(caused_by_device_loss(&validation_error(DeviceError::Lost)));
+        assert!(caused_by_device_loss(&DeviceError::Lost));
+        assert!(!caused_by_device_loss(&validation_error(
+            DeviceError::Invalid
+        )));
+    }
+
+    #[test]
+    fn keeps_first_error() {
+        let status = DeviceStatus::default();
+        status.record(GfxErrorKind::OutOfMemory);
+        status.record(GfxErrorKind::ValidationError("later".into()));
+
+        assert!(matches!(
+            status.take_error(),
+            Some(GfxErrorKind::OutOfMemory)
+        ));
+        assert!(status.take_error().is_none());
+    }
+}
@@ -1,5 +1,19 @@
-use crate::gfx::texture::TextureRef;
+use std::ops::Range;
 
+use crate::gfx::{texture::TextureRef, BlendMode};
+
+/// A range of quads in the batch which are drawn with the same texture, blend mode and camera.
 pub(super) struct RenderPass {
     pub(super) texture: TextureRef,
+    pub(super) blend_mode: BlendMode,
+    /// The index of the camera within the cameras of the current frame.
+    pub(super) camera: usize,
+    pub(super) first_index: u32,
+    pub(super) index_count: u32,
+}
+
+impl RenderPass {
+    pub(super) fn indices(&self) -> Range<u32> {
+        self.first_index..self.first_index + self.index_count
+    }
 }
@@ -1,7 +1,8 @@
-use crate::gfx::types::Shader;
+use crate::gfx::{types::Shader, BlendMode};
 
 pub struct PipelineDescriptor {
     pub vertex_shader: Shader,
     pub fragment_shader: Shader,
     pub texture_format: wgpu::TextureFormat,
+    pub blend_mode: BlendMode,
 }
@@ -1,6 +1,7 @@
 use crate::gfx::{
     texture::TextureRef,
     types::{Pipeline, Vertex},
+    BlendMode,
 };
 
 use super::{GfxContext, PipelineDescriptor};
@@ -8,6 +9,7 @@ use super::{GfxContext, PipelineDescriptor};
 pub(super) fn create_pipeline(
     device: &wgpu::Device,
     descriptor: &PipelineDescriptor,
+    blend_mode: BlendMode,
     default_texture: &TextureRef,
     camera_bind_group_layout: &wgpu::BindGroupLayout,
 ) -> Pipeline {
@@ -20,7 +22,7 @@ pub(super) fn create_pipeline(
         push_constant_ranges: &[],
     });
 
-    let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
         label: None,
         layout: Some(&pipeline_layout),
         vertex: wgpu::VertexState {
@@ -29,7 +31,7 @@ pub(super) fn create_pipeline(
             buffers: &[wgpu::VertexBufferLayout {
                 array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
                 step_mode: wgpu::VertexStepMode::Vertex,
-                attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2],
+                attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x4, 2 => Float32x2],
             }],
         },
         fragment: Some(wgpu::FragmentState {
@@ -37,7 +39,7 @@ pub(super) fn create_pipeline(
             entry_point: "main",
             targets: &[Some(wgpu::ColorTargetState {
                 format: descriptor.texture_format,
-                blend: Some(wgpu::BlendState::REPLACE),
+                blend: Some(blend_mode.into()),
                 write_mask: wgpu::ColorWrites::ALL,
             })],
         }),
@@ -53,9 +55,7 @@ pub(super) fn create_pipeline(
             alpha_to_coverage_enabled: false,
         },
         multiview: None,
-    });
-
-    render_pipeline
+    })
 }
 
 pub trait PipelineExt {
@@ -70,7 +70,7 @@ pub trait PipelineExt {
 }
 
 impl PipelineExt for GfxContext {
-    fn set_pipeline(&mut self, pipeline: &Pipeline) {
+    fn set_pipeline(&mut self, _pipeline: &Pipeline) {
         unimplemented!()
     }
 
@@ -83,6 +83,7 @@ impl PipelineExt for GfxContext {
         create_pipeline(
             &self.device,
             descriptor,
+            descriptor.blend_mode,
             default_texture,
             camera_bind_group_layout,
         )
@@ -0,0 +1,230 @@
+use image::RgbaImage;
+
+use crate::{
+    error::{ErrorKind, GfxErrorKind},
+    gfx::PresentMode,
+};
+
+/// The texture format used when rendering without a window.
+const OFFSCREEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Where the frames of a [`GfxContext`](super::GfxContext) end up.
+pub(super) enum RenderTarget {
+    /// Frames are presented to a window.
+    Surface {
+        surface: wgpu::Surface,
+        config: wgpu::SurfaceConfiguration,
+        /// The present modes supported by the surface.
+        present_modes: Vec<wgpu::PresentMode>,
+    },
+    /// Frames are rendered into a texture which can be read back with [`RenderTarget::read`].
+    Offscreen { texture: wgpu::Texture },
+}
+
+/// A frame acquired from a [`RenderTarget`].
+pub(super) enum Frame {
+    Surface(wgpu::SurfaceTexture),
+    Offscreen,
+}
+
+impl RenderTarget {
+    pub fn offscreen(device: &wgpu::Device, width: u32, height: u32) -> Self {
+        Self::Offscreen {
+            texture: create_offscreen_texture(device, width, height),
+        }
+    }
+
+    /// Returns the size of the target in physical pixels.
+    pub fn size(&self) -> (u32, u32) {
+        match self {
+            Self::Surface { config, .. } => (config.width, config.height),
+            Self::Offscreen { texture } => (texture.width(), texture.height()),
+        }
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        match self {
+            Self::Surface { config, .. } => config.format,
+            Self::Offscreen { .. } => OFFSCREEN_FORMAT,
+        }
+    }
+
+    /// Switches to the first supported mode of `present_mode`'s fallbacks and returns it.
+    /// Offscreen targets are not presented, so they always report [`PresentMode::Fifo`].
+    pub fn set_present_mode(
+        &mut self,
+        device: &wgpu::Device,
+        present_mode: PresentMode,
+    ) -> PresentMode {
+        let Self::Surface {
+            surface,
+            config,
+            present_modes,
+        } = self
+        else {
+            return PresentMode::Fifo;
+        };
+
+        let present_mode = present_mode
+            .fallbacks()
+            .iter()
+            .copied()
+            .find(|mode| present_modes.contains(&(*mode).into()))
+            .unwrap_or(PresentMode::Fifo);
+
+        config.present_mode = present_mode.into();
+        surface.configure(device, config);
+        present_mode
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        match self {
+            Self::Surface {
+                surface, config, ..
+            } => {
+                config.width = width;
+                config.height = height;
+                surface.configure(device, config);
+            }
+            Self::Offscreen { texture } => {
+                *texture = create_offscreen_texture(device, width, height);
+            }
+        }
+    }
+
+    /// Acquires the next frame. A lost or outdated surface is reconfigured once.
+    /// Returns `None` if the frame should be skipped, e.g. because the surface timed out.
+    pub fn acquire(
+        &self,
+        device: &wgpu::Device,
+    ) -> Result<Option<(Frame, wgpu::TextureView)>, wgpu::SurfaceError> {
+        match self {
+            Self::Surface {
+                surface, config, ..
+            } => {
+                let output = match surface.get_current_texture() {
+                    Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                        surface.configure(device, config);
+                        surface.get_current_texture()
+                    }
+                    output => output,
+                };
+
+                let output = match output {
+                    Ok(output) => output,
+                    Err(wgpu::SurfaceError::Timeout | wgpu::SurfaceError::Outdated) => {
+                        return Ok(None)
+                    }
+                    Err(e) => return Err(e),
+                };
+
+                let view = output
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor::default());
+
+                Ok(Some((Frame::Surface(output), view)))
+            }
+            Self::Offscreen { texture } => Ok(Some((
+                Frame::Offscreen,
+                texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            ))),
+        }
+    }
+
+    /// Copies the contents of an offscreen target into an image.
+    pub fn read(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<RgbaImage, ErrorKind> {
+        let texture = match self {
+            Self::Offscreen { texture } => texture,
+            Self::Surface { .. } => {
+                return Err(GfxErrorKind::ReadbackError(
+                    "Only headless contexts can be read back".into(),
+                )
+                .into());
+            }
+        };
+
+        let (width, height) = (texture.width(), texture.height());
+
+        // Rows of a texture-to-buffer copy have to be padded to a fixed alignment.
+        let unpadded_bytes_per_row = 4 * width;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (padded_bytes_per_row * height) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+
+        queue.submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+
+        match receiver.recv() {
+            Ok(Ok(())) => (),
+            Ok(Err(e)) => return Err(GfxErrorKind::ReadbackError(e.to_string()).into()),
+            Err(e) => return Err(GfxErrorKind::ReadbackError(e.to_string()).into()),
+        }
+
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        {
+            let data = slice.get_mapped_range();
+            for row in data.chunks(padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+            }
+        }
+        buffer.unmap();
+
+        RgbaImage::from_raw(width, height, pixels).ok_or_else(|| {
+            GfxErrorKind::ReadbackError("The frame has an unexpected size".into()).into()
+        })
+    }
+}
+
+impl Frame {
+    pub fn present(self) {
+        match self {
+            Self::Surface(output) => output.present(),
+            Self::Offscreen => (),
+        }
+    }
+}
+
+fn create_offscreen_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: OFFSCREEN_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
@@ -11,12 +11,16 @@ pub trait TextExt {
 
 impl TextExt for GfxContext {
     fn draw_text(&mut self, x: f32, y: f32, scale: f32, color: Color, text: &str) {
+        // Glyphs are rasterized in physical pixels to stay sharp on high DPI displays.
+        let scale_factor = self.scale_factor as f32;
+        let (width, height) = self.target.size();
+
         self.glyph_brush.queue(Section {
-            screen_position: (x, y),
-            bounds: (1024 as f32, 768 as f32),
+            screen_position: (x * scale_factor, y * scale_factor),
+            bounds: (width as f32, height as f32),
             text: vec![Text::new(text)
                 .with_color([color.r, color.g, color.b, color.a])
-                .with_scale(scale)],
+                .with_scale(scale * scale_factor)],
             ..Section::default()
         });
     }
@@ -0,0 +1,86 @@
+use winit::dpi::{LogicalPosition, LogicalSize};
+
+use crate::window::{self, CursorGrab, Fullscreen};
+
+use super::GfxContext;
+
+/// Adds support for interacting with the window. When running headless, these methods have no effect.
+pub trait WindowExt {
+    /// Enables or disables the input method editor (IME). While enabled, composed text is sent as
+    /// [`InputEvent::Ime`](crate::prelude::InputEvent::Ime) instead of individual key presses.
+    /// IME should only be enabled while the user is editing text.
+    fn set_ime_allowed(&mut self, allowed: bool);
+
+    /// Moves the IME candidate window to the given position in logical pixels, e.g. below the text cursor.
+    fn set_ime_position(&mut self, x: f32, y: f32);
+
+    fn set_title(&mut self, title: &str);
+
+    /// Returns the current fullscreen mode. This is [`Fullscreen::Windowed`] when running headless.
+    fn fullscreen(&self) -> Fullscreen;
+
+    /// Enters or leaves fullscreen on the monitor the window is currently shown on.
+    fn set_fullscreen(&mut self, fullscreen: Fullscreen);
+
+    /// Requests a new size for the window in logical pixels. [`EventHandler::resize`](crate::prelude::EventHandler::resize)
+    /// is called once the window was resized, which may not happen, e.g. in fullscreen.
+    fn set_window_size(&mut self, width: f32, height: f32);
+
+    fn set_cursor_visible(&mut self, visible: bool);
+
+    /// Keeps the cursor inside the window. If the requested mode is not supported, the other one is used instead.
+    fn set_cursor_grab(&mut self, grab: CursorGrab);
+}
+
+impl WindowExt for GfxContext {
+    fn set_ime_allowed(&mut self, allowed: bool) {
+        if let Some(window) = &self.window {
+            window.set_ime_allowed(allowed);
+        }
+    }
+
+    fn set_ime_position(&mut self, x: f32, y: f32) {
+        if let Some(window) = &self.window {
+            window.set_ime_position(LogicalPosition::new(x, y));
+        }
+    }
+
+    fn set_title(&mut self, title: &str) {
+        if let Some(window) = &self.window {
+            window.set_title(title);
+        }
+    }
+
+    fn fullscreen(&self) -> Fullscreen {
+        match self.window.as_ref().and_then(|window| window.fullscreen()) {
+            None => Fullscreen::Windowed,
+            Some(winit::window::Fullscreen::Borderless(_)) => Fullscreen::Borderless,
+            Some(winit::window::Fullscreen::Exclusive(_)) => Fullscreen::Exclusive,
+        }
+    }
+
+    fn set_fullscreen(&mut self, fullscreen: Fullscreen) {
+        if let Some(window) = &self.window {
+            let size = LogicalSize::new(self.config.width, self.config.height);
+            window.set_fullscreen(window::to_winit(fullscreen, window.current_monitor(), size));
+        }
+    }
+
+    fn set_window_size(&mut self, width: f32, height: f32) {
+        if let Some(window) = &self.window {
+            window.set_inner_size(LogicalSize::new(width, height));
+        }
+    }
+
+    fn set_cursor_visible(&mut self, visible: bool) {
+        if let Some(window) = &self.window {
+            window.set_cursor_visible(visible);
+        }
+    }
+
+    fn set_cursor_grab(&mut self, grab: CursorGrab) {
+        if let Some(window) = &self.window {
+            window::set_cursor_grab(window, grab);
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::{path::Path, sync::Arc};
 
-use image::RgbaImage;
+use image::{Rgba, RgbaImage};
 use wgpu::{BindGroup, BindGroupLayout};
 
 use crate::{error::ErrorKind, fs};
@@ -11,6 +11,7 @@ pub enum FilterMode {
     Nearest,
 }
 
+#[allow(clippy::from_over_into)]
 impl Into<wgpu::FilterMode> for FilterMode {
     fn into(self) -> wgpu::FilterMode {
         match self {
@@ -21,8 +22,12 @@ impl Into<wgpu::FilterMode> for FilterMode {
 }
 
 pub struct Texture {
+    // Only referenced by the bind group, but kept alive together with it.
+    #[allow(dead_code)]
     texture: wgpu::Texture,
+    #[allow(dead_code)]
     view: wgpu::TextureView,
+    #[allow(dead_code)]
     sampler: wgpu::Sampler,
     width: u32,
     height: u32,
@@ -30,9 +35,19 @@ pub struct Texture {
     bind_group: BindGroup,
 }
 
+/// A shared handle to a texture, as returned by [`TextureExt::create_texture`](crate::prelude::TextureExt::create_texture).
+/// Cloning it is cheap. Two handles are equal if they refer to the same texture.
 #[derive(Clone)]
 pub struct TextureRef(Arc<Texture>);
 
+impl PartialEq for TextureRef {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for TextureRef {}
+
 impl std::ops::Deref for TextureRef {
     type Target = Texture;
 
@@ -51,25 +66,24 @@ impl Texture {
     where
         P: AsRef<Path>,
     {
-        let bytes = fs::load_file(path)?;
-        let dyn_img = image::load_from_memory(&bytes).expect("Failed to create image");
-        let rgba_image: RgbaImage = dyn_img.to_rgba8();
+        let rgba_image = fs::load_image(path)?;
 
         Self::new(device, queue, &rgba_image, filter_mode)
     }
 
-    pub(crate) fn from_bytes(
+    /// Creates a texture consisting of a single pixel.
+    pub(crate) fn from_pixel(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-        bytes: Vec<u8>,
+        pixel: [u8; 4],
         filter_mode: FilterMode,
     ) -> Result<TextureRef, ErrorKind> {
-        //let data: Vec<u8> = vec![255, 255, 255, 255];
-        let rgba_image: RgbaImage = RgbaImage::from_raw(1, 1, bytes).expect("Failed to cr");
+        let rgba_image = RgbaImage::from_pixel(1, 1, Rgba(pixel));
 
         Self::new(device, queue, &rgba_image, filter_mode)
     }
 
+    #[allow(clippy::new_ret_no_self)]
     fn new(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
@@ -104,7 +118,7 @@ impl Texture {
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            &img,
+            img,
             wgpu::ImageDataLayout {
                 offset: 0,
                 bytes_per_row: Some(4 * dim.0),
@@ -1,8 +1,15 @@
 use std::borrow::Cow;
 
+use serde::{Deserialize, Serialize};
+
+use crate::error::{ErrorKind, GfxErrorKind};
+
 pub type Shader = wgpu::ShaderModule;
 pub type Pipeline = wgpu::RenderPipeline;
 pub type BufferUsages = wgpu::BufferUsages;
+pub type Features = wgpu::Features;
+pub type Limits = wgpu::Limits;
+pub type AdapterInfo = wgpu::AdapterInfo;
 
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable, Debug)]
@@ -19,6 +26,7 @@ pub enum ShaderStage {
     Compute = naga::ShaderStage::Compute as isize,
 }
 
+#[allow(clippy::from_over_into)]
 impl Into<naga::ShaderStage> for ShaderStage {
     fn into(self) -> naga::ShaderStage {
         match self {
@@ -29,15 +37,134 @@ impl Into<naga::ShaderStage> for ShaderStage {
     }
 }
 
-pub(super) fn create_shader(device: &wgpu::Device, stage: ShaderStage, src: &str) -> Shader {
+/// The graphics API used for rendering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Backend {
+    /// Uses the best API available on the current platform.
+    #[default]
+    Auto,
+    Vulkan,
+    Metal,
+    Dx12,
+    Dx11,
+    /// OpenGL, OpenGL ES or WebGL2.
+    Gl,
+}
+
+impl From<Backend> for wgpu::Backends {
+    fn from(backend: Backend) -> Self {
+        match backend {
+            Backend::Auto => wgpu::Backends::all(),
+            Backend::Vulkan => wgpu::Backends::VULKAN,
+            Backend::Metal => wgpu::Backends::METAL,
+            Backend::Dx12 => wgpu::Backends::DX12,
+            Backend::Dx11 => wgpu::Backends::DX11,
+            Backend::Gl => wgpu::Backends::GL,
+        }
+    }
+}
+
+/// Determines which adapter is chosen if more than one is available, e.g. on laptops with an integrated and a dedicated GPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PowerPreference {
+    LowPower,
+    #[default]
+    HighPerformance,
+}
+
+impl From<PowerPreference> for wgpu::PowerPreference {
+    fn from(power_preference: PowerPreference) -> Self {
+        match power_preference {
+            PowerPreference::LowPower => wgpu::PowerPreference::LowPower,
+            PowerPreference::HighPerformance => wgpu::PowerPreference::HighPerformance,
+        }
+    }
+}
+
+/// Determines how frames are presented to the window.
+///
+/// Not every mode is supported on every platform. If the requested mode is unsupported, the closest supported mode is used
+/// (see [`PresentMode::fallbacks`]), which is [`PresentMode::Fifo`] in the worst case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PresentMode {
+    /// Waits for the vertical blank (vsync). This is supported everywhere.
+    #[default]
+    Fifo,
+    /// Like [`PresentMode::Fifo`], but presents late frames immediately, which can cause tearing.
+    FifoRelaxed,
+    /// Does not wait for the vertical blank, but only shows the latest frame at the next one. This does not tear.
+    Mailbox,
+    /// Presents frames immediately, which can cause tearing.
+    Immediate,
+}
+
+impl PresentMode {
+    /// Returns the modes which are tried in order, starting with `self`.
+    pub fn fallbacks(self) -> &'static [PresentMode] {
+        use PresentMode::*;
+
+        match self {
+            Fifo => &[Fifo],
+            FifoRelaxed => &[FifoRelaxed, Fifo],
+            Mailbox => &[Mailbox, Immediate, Fifo],
+            Immediate => &[Immediate, Mailbox, Fifo],
+        }
+    }
+}
+
+impl From<PresentMode> for wgpu::PresentMode {
+    fn from(present_mode: PresentMode) -> Self {
+        match present_mode {
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+            PresentMode::FifoRelaxed => wgpu::PresentMode::FifoRelaxed,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+        }
+    }
+}
+
+/// Compiles a GLSL shader. The source is checked with naga first, so that errors are returned instead of
+/// being reported by the device.
+pub(super) fn create_shader(
+    device: &wgpu::Device,
+    stage: ShaderStage,
+    src: &str,
+) -> Result<Shader, ErrorKind> {
+    let shader_error =
+        |message: String| ErrorKind::Gfx(GfxErrorKind::ShaderError { stage, message });
+
+    let naga_stage: naga::ShaderStage = stage.into();
+    let module = naga::front::glsl::Frontend::default()
+        .parse(&naga::front::glsl::Options::from(naga_stage), src)
+        .map_err(|errors| {
+            let errors: Vec<_> = errors
+                .iter()
+                .map(|e| {
+                    let location = e.meta.location(src);
+                    format!(
+                        "{}:{}: {}",
+                        location.line_number, location.line_position, e.kind
+                    )
+                })
+                .collect();
+            shader_error(errors.join("\n"))
+        })?;
+
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .map_err(|e| shader_error(e.into_inner().to_string()))?;
+
     let source = wgpu::ShaderSource::Glsl {
         shader: Cow::Borrowed(src),
         stage: stage.into(),
         defines: naga::FastHashMap::default(),
     };
 
-    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+    Ok(device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: None,
         source,
-    })
+    }))
 }
@@ -1,38 +1,146 @@
-use winit::event::{ElementState, VirtualKeyCode};
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+pub use winit::event::{
+    ElementState, ModifiersState, MouseButton, MouseScrollDelta, TouchPhase, VirtualKeyCode,
+};
 
-mod key;
+mod bindings;
+mod gamepad;
+mod gesture;
+pub(crate) mod recording;
+mod state;
 
-#[derive(Debug, Clone, Copy)]
+pub use bindings::{AxisBinding, Bindings};
+pub(crate) use gamepad::Gamepads;
+#[cfg(feature = "gilrs")]
+pub use gamepad::GilrsBackend;
+pub use gamepad::{
+    GamepadAxis, GamepadBackend, GamepadButton, GamepadEvent, GamepadId, VirtualGamepads,
+};
+pub use gesture::{Gesture, GestureConfig, GestureRecognizer};
+pub(crate) use recording::Recorder;
+pub use recording::{RecordedEvent, Recording};
+pub use state::{Button, InputState};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct KeyEvent {
     pub state: winit::event::ElementState,
     pub key: winit::event::VirtualKeyCode,
+    /// The modifier keys (shift, ctrl, alt, logo) held down when the event occurred.
+    pub modifiers: ModifiersState,
+}
+
+/// A position on screen, given both in physical and in logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CursorPosition {
+    pub physical: Vec2,
+    pub logical: Vec2,
+}
+
+/// An event of the input method editor (IME), which is used to compose text in languages such as Chinese or Japanese.
+/// These events are only sent after IME was enabled with [`WindowExt::set_ime_allowed`](crate::prelude::WindowExt::set_ime_allowed).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ImeEvent {
+    /// The IME was enabled.
+    Enabled,
+    /// The text currently being composed changed. This text should be displayed in place of the cursor,
+    /// but not be inserted yet. An empty `text` means the composition was cleared.
+    Preedit {
+        text: String,
+        /// The byte range of the cursor within `text`, or `None` if it should be hidden.
+        cursor: Option<(usize, usize)>,
+    },
+    /// The composition is complete and `text` should be inserted.
+    Commit(String),
+    /// The IME was disabled.
+    Disabled,
+}
+
+impl From<winit::event::Ime> for ImeEvent {
+    fn from(ime: winit::event::Ime) -> Self {
+        use winit::event::Ime;
+
+        match ime {
+            Ime::Enabled => Self::Enabled,
+            Ime::Preedit(text, cursor) => Self::Preedit { text, cursor },
+            Ime::Commit(text) => Self::Commit(text),
+            Ime::Disabled => Self::Disabled,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// An input event, which is passed to [`EventHandler::input`](crate::prelude::EventHandler::input).
+///
+/// Events are not `Copy`, since [`InputEvent::Ime`] contains text. Use `clone` to keep an event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum InputEvent {
     Key(KeyEvent),
+    /// A character was typed. Control characters such as backspace or enter are only sent as [`InputEvent::Key`].
+    Text(char),
+    Ime(ImeEvent),
+    /// The cursor was moved within the window.
+    MouseMoved {
+        position: CursorPosition,
+    },
+    MouseButton {
+        button: MouseButton,
+        state: ElementState,
+    },
+    /// The mouse wheel or touchpad was scrolled.
+    MouseWheel {
+        delta: MouseScrollDelta,
+    },
+    CursorEntered,
+    CursorLeft,
+    /// The window gained (`true`) or lost (`false`) focus.
+    Focused(bool),
+    /// A gamepad was connected or disconnected, or one of its buttons or axes changed.
+    Gamepad(GamepadEvent),
+    /// A finger touched, moved on or left the touch screen. `id` distinguishes multiple fingers.
+    Touch {
+        id: u64,
+        phase: TouchPhase,
+        position: CursorPosition,
+    },
+    /// A gesture was recognized from touch input.
+    Gesture(Gesture),
 }
 
 impl InputEvent {
-    pub fn is_key_pressed(self, key: VirtualKeyCode) -> bool {
+    pub fn is_key_pressed(&self, key: VirtualKeyCode) -> bool {
         self.is_key_state(ElementState::Pressed, key)
     }
 
-    pub fn is_key_released(self, key: VirtualKeyCode) -> bool {
+    pub fn is_key_released(&self, key: VirtualKeyCode) -> bool {
         self.is_key_state(ElementState::Released, key)
     }
 
-    fn is_key_state(self, state: ElementState, key: VirtualKeyCode) -> bool {
+    pub fn is_mouse_pressed(&self, button: MouseButton) -> bool {
+        self.is_mouse_state(ElementState::Pressed, button)
+    }
+
+    pub fn is_mouse_released(&self, button: MouseButton) -> bool {
+        self.is_mouse_state(ElementState::Released, button)
+    }
+
+    fn is_key_state(&self, state: ElementState, key: VirtualKeyCode) -> bool {
         match self {
             InputEvent::Key(KeyEvent {
                 state: _state,
                 key: _key,
-            }) => {
-                if state == _state && key == _key {
-                    return true;
-                }
-            }
+                ..
+            }) => state == *_state && key == *_key,
+            _ => false,
+        }
+    }
+
+    fn is_mouse_state(&self, state: ElementState, button: MouseButton) -> bool {
+        match self {
+            InputEvent::MouseButton {
+                button: _button,
+                state: _state,
+            } => state == *_state && button == *_button,
+            _ => false,
         }
-        false
     }
 }
@@ -0,0 +1,112 @@
+use std::{collections::BTreeMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::ErrorKind, fs};
+
+use super::{Button, GamepadAxis};
+
+/// An input which reports a value between `-1` and `1` for a named axis.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AxisBinding {
+    /// Two buttons, reporting `-1` while `negative` and `1` while `positive` is held down.
+    Buttons { negative: Button, positive: Button },
+    /// An analog gamepad axis.
+    Gamepad(GamepadAxis),
+}
+
+/// Maps named actions (e.g. `"jump"`) and axes (e.g. `"move_x"`) to keys, mouse buttons and gamepad inputs.
+///
+/// Bindings are queried through [`InputState`](crate::prelude::InputState) and can be saved to and loaded from
+/// [RON](https://github.com/ron-rs/ron) files, which allows players to remap their controls.
+///
+/// # Example
+///
+/// ```
+/// use kaffee::prelude::*;
+///
+/// let mut bindings = Bindings::default();
+/// bindings
+///     .bind("jump", VirtualKeyCode::Space)
+///     .bind("jump", GamepadButton::South)
+///     .bind_axis(
+///         "move_x",
+///         AxisBinding::Buttons {
+///             negative: VirtualKeyCode::Left.into(),
+///             positive: VirtualKeyCode::Right.into(),
+///         },
+///     );
+///
+/// let mut input = InputState::default();
+/// input.set_bindings(bindings);
+/// input.process(&InputEvent::Key(KeyEvent {
+///     state: ElementState::Pressed,
+///     key: VirtualKeyCode::Right,
+///     modifiers: ModifiersState::empty(),
+/// }));
+///
+/// assert_eq!(input.axis("move_x"), 1.);
+/// assert!(!input.action_down("jump"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Bindings {
+    actions: BTreeMap<String, Vec<Button>>,
+    axes: BTreeMap<String, Vec<AxisBinding>>,
+}
+
+impl Bindings {
+    /// Loads bindings from a RON file.
+    pub fn load<P>(path: P) -> Result<Self, ErrorKind>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let bytes = fs::load_file(path)?;
+        ron::de::from_bytes(&bytes).map_err(|e| ErrorKind::parse(Some(path.to_owned()), e))
+    }
+
+    /// Saves the bindings to a RON file, replacing it if it already exists.
+    pub fn save<P>(&self, path: P) -> Result<(), ErrorKind>
+    where
+        P: AsRef<Path>,
+    {
+        let ron = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| ErrorKind::parse(Some(path.as_ref().to_owned()), e))?;
+        fs::save_file(path, ron.as_bytes())
+    }
+
+    /// Adds a binding to an action. An action can have any number of bindings.
+    pub fn bind(&mut self, action: &str, button: impl Into<Button>) -> &mut Self {
+        self.actions
+            .entry(action.to_owned())
+            .or_default()
+            .push(button.into());
+        self
+    }
+
+    /// Adds a binding to an axis. The values of all bindings of an axis are summed up.
+    pub fn bind_axis(&mut self, axis: &str, binding: AxisBinding) -> &mut Self {
+        self.axes.entry(axis.to_owned()).or_default().push(binding);
+        self
+    }
+
+    /// Removes all bindings of an action.
+    pub fn unbind(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+
+    /// Removes all bindings of an axis.
+    pub fn unbind_axis(&mut self, axis: &str) {
+        self.axes.remove(axis);
+    }
+
+    /// Returns the buttons bound to an action.
+    pub fn action(&self, action: &str) -> &[Button] {
+        self.actions.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the bindings of an axis.
+    pub fn axis(&self, axis: &str) -> &[AxisBinding] {
+        self.axes.get(axis).map_or(&[], Vec::as_slice)
+    }
+}
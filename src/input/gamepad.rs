@@ -0,0 +1,286 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet, VecDeque},
+    rc::Rc,
+};
+
+use serde::{Deserialize, Serialize};
+use winit::event::ElementState;
+
+#[cfg(feature = "gilrs")]
+mod gilrs_backend;
+
+#[cfg(feature = "gilrs")]
+pub use gilrs_backend::GilrsBackend;
+
+/// Identifies a connected gamepad. Ids may be reused after a gamepad was disconnected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GamepadId(pub usize);
+
+/// A button on a gamepad, named after its position on a standard controller layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+    /// The bottom face button (A on Xbox, Cross on PlayStation controllers).
+    South,
+    /// The right face button (B on Xbox, Circle on PlayStation controllers).
+    East,
+    /// The top face button (Y on Xbox, Triangle on PlayStation controllers).
+    North,
+    /// The left face button (X on Xbox, Square on PlayStation controllers).
+    West,
+    LeftBumper,
+    LeftTrigger,
+    RightBumper,
+    RightTrigger,
+    Select,
+    Start,
+    Mode,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// An analog axis on a gamepad. Values range from `-1` to `1`, with positive values pointing right and up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    /// The left trigger, ranging from `0` to `1`.
+    LeftTrigger,
+    /// The right trigger, ranging from `0` to `1`.
+    RightTrigger,
+}
+
+/// An event reported by a [`GamepadBackend`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GamepadEvent {
+    Connected(GamepadId),
+    Disconnected(GamepadId),
+    Button {
+        id: GamepadId,
+        button: GamepadButton,
+        state: ElementState,
+    },
+    /// An axis changed. The deadzone configured in [`Config`](crate::prelude::Config) has already been applied.
+    Axis {
+        id: GamepadId,
+        axis: GamepadAxis,
+        value: f32,
+    },
+}
+
+/// A source of gamepad events, which is polled once per iteration of the event loop.
+///
+/// With the `gilrs` feature (enabled by default), [`GilrsBackend`] is used. [`VirtualGamepads`] can be used
+/// to drive an application without any hardware.
+pub trait GamepadBackend {
+    /// Returns the next pending event, or `None` if there is none.
+    fn poll(&mut self) -> Option<GamepadEvent>;
+}
+
+/// A [`GamepadBackend`] which reports the events sent to it from code, e.g. in tests.
+/// Clones share the same event queue, so one clone can be passed to the app while the other one is used to send events.
+///
+/// # Example
+///
+/// ```
+/// use kaffee::prelude::*;
+///
+/// let gamepads = VirtualGamepads::default();
+/// let mut backend = gamepads.clone();
+///
+/// let pad = GamepadId(0);
+/// gamepads.connect(pad);
+/// gamepads.press(pad, GamepadButton::South);
+///
+/// assert_eq!(backend.poll(), Some(GamepadEvent::Connected(pad)));
+/// assert_eq!(
+///     backend.poll(),
+///     Some(GamepadEvent::Button {
+///         id: pad,
+///         button: GamepadButton::South,
+///         state: ElementState::Pressed,
+///     })
+/// );
+/// assert_eq!(backend.poll(), None);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct VirtualGamepads {
+    events: Rc<RefCell<VecDeque<GamepadEvent>>>,
+}
+
+impl VirtualGamepads {
+    pub fn connect(&self, id: GamepadId) {
+        self.send(GamepadEvent::Connected(id));
+    }
+
+    pub fn disconnect(&self, id: GamepadId) {
+        self.send(GamepadEvent::Disconnected(id));
+    }
+
+    pub fn press(&self, id: GamepadId, button: GamepadButton) {
+        self.send(GamepadEvent::Button {
+            id,
+            button,
+            state: ElementState::Pressed,
+        });
+    }
+
+    pub fn release(&self, id: GamepadId, button: GamepadButton) {
+        self.send(GamepadEvent::Button {
+            id,
+            button,
+            state: ElementState::Released,
+        });
+    }
+
+    pub fn set_axis(&self, id: GamepadId, axis: GamepadAxis, value: f32) {
+        self.send(GamepadEvent::Axis { id, axis, value });
+    }
+
+    fn send(&self, event: GamepadEvent) {
+        self.events.borrow_mut().push_back(event);
+    }
+}
+
+impl GamepadBackend for VirtualGamepads {
+    fn poll(&mut self) -> Option<GamepadEvent> {
+        self.events.borrow_mut().pop_front()
+    }
+}
+
+/// Polls a [`GamepadBackend`] and applies the deadzone to its axis events.
+pub(crate) struct Gamepads {
+    backend: Option<Box<dyn GamepadBackend>>,
+    deadzone: f32,
+    axes: HashMap<(GamepadId, GamepadAxis), f32>,
+    connected: HashSet<GamepadId>,
+}
+
+impl Gamepads {
+    /// Uses the default backend, if one is enabled and available on this platform.
+    pub fn new(deadzone: f32) -> Self {
+        Self {
+            backend: default_backend(),
+            deadzone: deadzone.clamp(0., 0.99),
+            axes: HashMap::new(),
+            connected: HashSet::new(),
+        }
+    }
+
+    pub fn set_backend(&mut self, backend: Box<dyn GamepadBackend>) {
+        self.backend = Some(backend);
+        self.axes.clear();
+        self.connected.clear();
+    }
+
+    /// Returns `true` if gamepads are polled at all, i.e. a backend is enabled and available.
+    pub fn has_backend(&self) -> bool {
+        self.backend.is_some()
+    }
+
+    /// Returns `true` while at least one gamepad is connected.
+    pub fn any_connected(&self) -> bool {
+        !self.connected.is_empty()
+    }
+
+    /// Returns the next event, skipping axis events which did not change the value after applying the deadzone.
+    pub fn poll(&mut self) -> Option<GamepadEvent> {
+        let backend = self.backend.as_mut()?;
+
+        loop {
+            let event = match backend.poll()? {
+                GamepadEvent::Axis { id, axis, value } => {
+                    let value = apply_deadzone(value, self.deadzone);
+                    if self.axes.insert((id, axis), value) == Some(value) {
+                        continue;
+                    }
+                    GamepadEvent::Axis { id, axis, value }
+                }
+                GamepadEvent::Connected(id) => {
+                    self.connected.insert(id);
+                    GamepadEvent::Connected(id)
+                }
+                GamepadEvent::Disconnected(id) => {
+                    self.axes.retain(|(pad, _), _| *pad != id);
+                    self.connected.remove(&id);
+                    GamepadEvent::Disconnected(id)
+                }
+                event => event,
+            };
+
+            return Some(event);
+        }
+    }
+}
+
+fn default_backend() -> Option<Box<dyn GamepadBackend>> {
+    #[cfg(feature = "gilrs")]
+    if let Some(backend) = GilrsBackend::new() {
+        return Some(Box::new(backend));
+    }
+
+    None
+}
+
+/// Maps values within the deadzone to `0` and rescales the remaining range, so that values still start at `0`.
+fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    let magnitude = value.abs().min(1.);
+    if magnitude <= deadzone {
+        return 0.;
+    }
+
+    value.signum() * (magnitude - deadzone) / (1. - deadzone)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_connected_gamepads() {
+        let virtual_gamepads = VirtualGamepads::default();
+        let mut gamepads = Gamepads::new(0.1);
+        gamepads.set_backend(Box::new(virtual_gamepads.clone()));
+
+        virtual_gamepads.connect(GamepadId(0));
+        virtual_gamepads.connect(GamepadId(1));
+        while gamepads.poll().is_some() {}
+        assert!(gamepads.any_connected());
+
+        virtual_gamepads.disconnect(GamepadId(0));
+        virtual_gamepads.disconnect(GamepadId(1));
+        while gamepads.poll().is_some() {}
+        assert!(!gamepads.any_connected());
+    }
+
+    #[test]
+    fn deadzone() {
+        let virtual_gamepads = VirtualGamepads::default();
+        let mut gamepads = Gamepads::new(0.5);
+        gamepads.set_backend(Box::new(virtual_gamepads.clone()));
+
+        let pad = GamepadId(0);
+        virtual_gamepads.set_axis(pad, GamepadAxis::LeftStickX, 0.1);
+        virtual_gamepads.set_axis(pad, GamepadAxis::LeftStickX, 0.15);
+        virtual_gamepads.set_axis(pad, GamepadAxis::LeftStickX, -0.75);
+
+        let axis = |value| {
+            Some(GamepadEvent::Axis {
+                id: pad,
+                axis: GamepadAxis::LeftStickX,
+                value,
+            })
+        };
+
+        // The second value lies within the deadzone as well, so it is skipped.
+        assert_eq!(gamepads.poll(), axis(0.));
+        assert_eq!(gamepads.poll(), axis(-0.5));
+        assert_eq!(gamepads.poll(), None);
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::VecDeque;
+
+use gilrs::{Axis, Button, EventType, Gilrs};
+use winit::event::ElementState;
+
+use super::{GamepadAxis, GamepadBackend, GamepadButton, GamepadEvent, GamepadId};
+
+/// The default [`GamepadBackend`], based on [gilrs](https://gitlab.com/gilrs-project/gilrs).
+pub struct GilrsBackend {
+    gilrs: Gilrs,
+    /// Gamepads which were already connected on startup are reported as connected once.
+    connected: VecDeque<GamepadEvent>,
+}
+
+impl GilrsBackend {
+    /// Returns `None` if gilrs could not be initialized, e.g. because the platform is not supported.
+    pub fn new() -> Option<Self> {
+        match Gilrs::new() {
+            Ok(gilrs) => {
+                let connected = gilrs
+                    .gamepads()
+                    .map(|(id, _)| GamepadEvent::Connected(GamepadId(id.into())))
+                    .collect();
+                Some(Self { gilrs, connected })
+            }
+            Err(why) => {
+                log::warn!("Gamepad support is unavailable: {why}");
+                None
+            }
+        }
+    }
+}
+
+impl GamepadBackend for GilrsBackend {
+    fn poll(&mut self) -> Option<GamepadEvent> {
+        if let Some(event) = self.connected.pop_front() {
+            return Some(event);
+        }
+
+        while let Some(gilrs::Event { id, event, .. }) = self.gilrs.next_event() {
+            let id = GamepadId(id.into());
+
+            let event = match event {
+                EventType::Connected => GamepadEvent::Connected(id),
+                EventType::Disconnected => GamepadEvent::Disconnected(id),
+                EventType::ButtonPressed(button, _) | EventType::ButtonReleased(button, _) => {
+                    let Some(button) = map_button(button) else {
+                        continue;
+                    };
+                    let state = match event {
+                        EventType::ButtonPressed(..) => ElementState::Pressed,
+                        _ => ElementState::Released,
+                    };
+                    GamepadEvent::Button { id, button, state }
+                }
+                // Analog triggers are reported as buttons with a value by gilrs.
+                EventType::ButtonChanged(Button::LeftTrigger2, value, _) => GamepadEvent::Axis {
+                    id,
+                    axis: GamepadAxis::LeftTrigger,
+                    value,
+                },
+                EventType::ButtonChanged(Button::RightTrigger2, value, _) => GamepadEvent::Axis {
+                    id,
+                    axis: GamepadAxis::RightTrigger,
+                    value,
+                },
+                EventType::AxisChanged(axis, value, _) => {
+                    let Some(axis) = map_axis(axis) else {
+                        continue;
+                    };
+                    GamepadEvent::Axis { id, axis, value }
+                }
+                _ => continue,
+            };
+
+            return Some(event);
+        }
+
+        None
+    }
+}
+
+fn map_button(button: Button) -> Option<GamepadButton> {
+    let button = match button {
+        Button::South => GamepadButton::South,
+        Button::East => GamepadButton::East,
+        Button::North => GamepadButton::North,
+        Button::West => GamepadButton::West,
+        Button::LeftTrigger => GamepadButton::LeftBumper,
+        Button::LeftTrigger2 => GamepadButton::LeftTrigger,
+        Button::RightTrigger => GamepadButton::RightBumper,
+        Button::RightTrigger2 => GamepadButton::RightTrigger,
+        Button::Select => GamepadButton::Select,
+        Button::Start => GamepadButton::Start,
+        Button::Mode => GamepadButton::Mode,
+        Button::LeftThumb => GamepadButton::LeftThumb,
+        Button::RightThumb => GamepadButton::RightThumb,
+        Button::DPadUp => GamepadButton::DPadUp,
+        Button::DPadDown => GamepadButton::DPadDown,
+        Button::DPadLeft => GamepadButton::DPadLeft,
+        Button::DPadRight => GamepadButton::DPadRight,
+        Button::C | Button::Z | Button::Unknown => return None,
+    };
+
+    Some(button)
+}
+
+fn map_axis(axis: Axis) -> Option<GamepadAxis> {
+    let axis = match axis {
+        Axis::LeftStickX => GamepadAxis::LeftStickX,
+        Axis::LeftStickY => GamepadAxis::LeftStickY,
+        Axis::RightStickX => GamepadAxis::RightStickX,
+        Axis::RightStickY => GamepadAxis::RightStickY,
+        _ => return None,
+    };
+
+    Some(axis)
+}
@@ -0,0 +1,477 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    f32::consts::{PI, TAU},
+    time::{Duration, Instant},
+};
+
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+use winit::event::TouchPhase;
+
+/// A high-level gesture recognized from touch input. All positions are given in logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Gesture {
+    /// A finger touched the screen briefly without moving.
+    Tap { position: Vec2 },
+    /// A second tap followed shortly after a tap at the same position. Both taps are reported as [`Gesture::Tap`] as well.
+    DoubleTap { position: Vec2 },
+    /// A finger was held on the screen without moving.
+    LongPress { position: Vec2 },
+    /// A single finger is being dragged across the screen.
+    Pan {
+        phase: TouchPhase,
+        position: Vec2,
+        /// The movement since the last pan event.
+        delta: Vec2,
+    },
+    /// Two fingers moved closer together (`scale < 1`) or further apart (`scale > 1`).
+    Pinch {
+        center: Vec2,
+        /// The change of the distance between both fingers since the last pinch event.
+        scale: f32,
+    },
+    /// Two fingers rotated around their center.
+    Rotate {
+        center: Vec2,
+        /// The change of the angle since the last rotate event, in radians. Positive values are clockwise on screen.
+        angle: f32,
+    },
+}
+
+/// Thresholds used by the [`GestureRecognizer`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GestureConfig {
+    /// How far a finger may move before a tap becomes a pan, in logical pixels.
+    pub tap_slop: f32,
+    /// The maximum time between two taps of a double tap.
+    pub double_tap_time: Duration,
+    /// How long a finger has to be held for a long press.
+    pub long_press_time: Duration,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            tap_slop: 10.,
+            double_tap_time: Duration::from_millis(300),
+            long_press_time: Duration::from_millis(500),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Touch {
+    start: Vec2,
+    position: Vec2,
+    started_at: Instant,
+    /// Set once the touch can no longer become a tap or long press.
+    moved: bool,
+    long_pressed: bool,
+}
+
+/// Turns touch input into [`Gesture`]s.
+///
+/// The application feeds all touch events into a recognizer and sends the results as
+/// [`InputEvent::Gesture`](crate::prelude::InputEvent::Gesture). A recognizer can also be driven manually, e.g. in tests.
+///
+/// # Example
+///
+/// ```
+/// use std::time::{Duration, Instant};
+///
+/// use kaffee::prelude::*;
+///
+/// let mut gestures = GestureRecognizer::default();
+/// let start = Instant::now();
+///
+/// gestures.touch(0, TouchPhase::Started, Vec2::new(10., 10.), start);
+/// gestures.touch(0, TouchPhase::Ended, Vec2::new(11., 10.), start + Duration::from_millis(80));
+///
+/// assert_eq!(
+///     gestures.poll(),
+///     Some(Gesture::Tap {
+///         position: Vec2::new(11., 10.)
+///     })
+/// );
+/// assert_eq!(gestures.poll(), None);
+/// ```
+#[derive(Debug, Default)]
+pub struct GestureRecognizer {
+    config: GestureConfig,
+    touches: BTreeMap<u64, Touch>,
+    panning: bool,
+    last_tap: Option<(Instant, Vec2)>,
+    gestures: VecDeque<Gesture>,
+}
+
+impl GestureRecognizer {
+    pub fn new(config: GestureConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    /// Returns `true` while at least one finger touches the screen.
+    pub fn is_active(&self) -> bool {
+        !self.touches.is_empty()
+    }
+
+    /// Feeds a touch event into the recognizer. `time` is the time at which the event occurred.
+    pub fn touch(&mut self, id: u64, phase: TouchPhase, position: Vec2, time: Instant) {
+        match phase {
+            TouchPhase::Started => self.start(id, position, time),
+            TouchPhase::Moved => self.moved(id, position),
+            TouchPhase::Ended | TouchPhase::Cancelled => self.end(id, position, time, phase),
+        }
+    }
+
+    /// Advances the recognizer to `time`, which reports long presses. This has to be called regularly, e.g. every frame.
+    pub fn update(&mut self, time: Instant) {
+        if self.touches.len() != 1 {
+            return;
+        }
+
+        for touch in self.touches.values_mut() {
+            if !touch.moved
+                && !touch.long_pressed
+                && time.duration_since(touch.started_at) >= self.config.long_press_time
+            {
+                touch.long_pressed = true;
+                self.gestures.push_back(Gesture::LongPress {
+                    position: touch.position,
+                });
+            }
+        }
+    }
+
+    /// Returns the next recognized gesture, or `None` if there is none.
+    pub fn poll(&mut self) -> Option<Gesture> {
+        self.gestures.pop_front()
+    }
+
+    fn start(&mut self, id: u64, position: Vec2, time: Instant) {
+        // A second finger turns the gesture into a pinch or rotation, which cannot become a tap anymore.
+        let multi_touch = !self.touches.is_empty();
+        if let Some(first) = self.touches.values().next() {
+            self.end_pan(TouchPhase::Ended, first.position);
+        }
+        for touch in self.touches.values_mut() {
+            touch.moved = true;
+        }
+
+        self.touches.insert(
+            id,
+            Touch {
+                start: position,
+                position,
+                started_at: time,
+                moved: multi_touch,
+                long_pressed: false,
+            },
+        );
+    }
+
+    fn moved(&mut self, id: u64, position: Vec2) {
+        let before = self.pair();
+        let Some(touch) = self.touches.get_mut(&id) else {
+            return;
+        };
+
+        let previous = touch.position;
+        touch.position = position;
+
+        if position.distance(touch.start) > self.config.tap_slop {
+            touch.moved = true;
+        }
+        let moved = touch.moved;
+
+        if self.touches.len() > 1 {
+            match (before, self.pair()) {
+                (Some(before), Some(after)) if before != after => self.transform(before, after),
+                _ => (),
+            }
+        } else if moved {
+            let phase = if self.panning {
+                TouchPhase::Moved
+            } else {
+                TouchPhase::Started
+            };
+            self.panning = true;
+            self.gestures.push_back(Gesture::Pan {
+                phase,
+                position,
+                delta: position - previous,
+            });
+        }
+    }
+
+    fn end(&mut self, id: u64, position: Vec2, time: Instant, phase: TouchPhase) {
+        let Some(touch) = self.touches.remove(&id) else {
+            return;
+        };
+
+        if self.touches.is_empty() && self.panning {
+            self.end_pan(phase, position);
+            return;
+        }
+
+        let is_tap = phase == TouchPhase::Ended
+            && !touch.moved
+            && !touch.long_pressed
+            && time.duration_since(touch.started_at) < self.config.long_press_time;
+
+        if is_tap {
+            self.gestures.push_back(Gesture::Tap { position });
+
+            match self.last_tap.take() {
+                Some((last, last_position))
+                    if time.duration_since(last) <= self.config.double_tap_time
+                        && position.distance(last_position) <= self.config.tap_slop =>
+                {
+                    self.gestures.push_back(Gesture::DoubleTap { position });
+                }
+                _ => self.last_tap = Some((time, position)),
+            }
+        }
+    }
+
+    fn end_pan(&mut self, phase: TouchPhase, position: Vec2) {
+        if std::mem::take(&mut self.panning) {
+            self.gestures.push_back(Gesture::Pan {
+                phase,
+                position,
+                delta: Vec2::ZERO,
+            });
+        }
+    }
+
+    /// Returns the positions of the two touches used for pinching and rotating.
+    fn pair(&self) -> Option<(Vec2, Vec2)> {
+        let mut touches = self.touches.values().map(|touch| touch.position);
+        Some((touches.next()?, touches.next()?))
+    }
+
+    /// Reports the pinch and rotation between two positions of a pair of touches.
+    fn transform(&mut self, (a0, b0): (Vec2, Vec2), (a1, b1): (Vec2, Vec2)) {
+        let center = (a1 + b1) / 2.;
+        let (before, after) = (b0 - a0, b1 - a1);
+
+        if before.length() > 0. && after.length() != before.length() {
+            self.gestures.push_back(Gesture::Pinch {
+                center,
+                scale: after.length() / before.length(),
+            });
+        }
+
+        let angle = wrap_angle(after.y.atan2(after.x) - before.y.atan2(before.x));
+        if angle != 0. {
+            self.gestures.push_back(Gesture::Rotate { center, angle });
+        }
+    }
+}
+
+/// Wraps an angle into the range `-PI..=PI`.
+fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(TAU) - PI
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    /// Feeds touch events into a recognizer, `ms` milliseconds after the start of the test.
+    struct Fingers {
+        gestures: GestureRecognizer,
+        start: Instant,
+    }
+
+    impl Fingers {
+        fn new() -> Self {
+            Self {
+                gestures: GestureRecognizer::default(),
+                start: Instant::now(),
+            }
+        }
+
+        fn touch(&mut self, id: u64, phase: TouchPhase, x: f32, y: f32, ms: u64) -> &mut Self {
+            let time = self.start + Duration::from_millis(ms);
+            self.gestures.touch(id, phase, Vec2::new(x, y), time);
+            self
+        }
+
+        fn update(&mut self, ms: u64) -> &mut Self {
+            self.gestures.update(self.start + Duration::from_millis(ms));
+            self
+        }
+
+        fn poll(&mut self) -> Vec<Gesture> {
+            std::iter::from_fn(|| self.gestures.poll()).collect()
+        }
+    }
+
+    fn assert_near(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-4, "{a} != {b}");
+    }
+
+    #[test]
+    fn double_tap() {
+        let mut fingers = Fingers::new();
+        let position = Vec2::new(20., 20.);
+
+        fingers
+            .touch(0, TouchPhase::Started, 20., 20., 0)
+            .touch(0, TouchPhase::Ended, 20., 20., 50)
+            .touch(0, TouchPhase::Started, 22., 20., 150)
+            .touch(0, TouchPhase::Ended, 20., 20., 200);
+
+        assert_eq!(
+            fingers.poll(),
+            vec![
+                Gesture::Tap { position },
+                Gesture::Tap { position },
+                Gesture::DoubleTap { position },
+            ]
+        );
+    }
+
+    #[test]
+    fn slow_taps_are_no_double_tap() {
+        let mut fingers = Fingers::new();
+
+        fingers
+            .touch(0, TouchPhase::Started, 20., 20., 0)
+            .touch(0, TouchPhase::Ended, 20., 20., 50)
+            .touch(0, TouchPhase::Started, 20., 20., 500)
+            .touch(0, TouchPhase::Ended, 20., 20., 550);
+
+        let gestures = fingers.poll();
+        assert_eq!(gestures.len(), 2);
+        assert!(gestures
+            .iter()
+            .all(|gesture| matches!(gesture, Gesture::Tap { .. })));
+    }
+
+    #[test]
+    fn long_press() {
+        let mut fingers = Fingers::new();
+
+        fingers.touch(0, TouchPhase::Started, 5., 5., 0).update(400);
+        assert_eq!(fingers.poll(), vec![]);
+
+        fingers.update(500).update(600);
+        assert_eq!(
+            fingers.poll(),
+            vec![Gesture::LongPress {
+                position: Vec2::new(5., 5.)
+            }]
+        );
+
+        // A long press is not a tap.
+        fingers.touch(0, TouchPhase::Ended, 5., 5., 700);
+        assert_eq!(fingers.poll(), vec![]);
+        assert!(!fingers.gestures.is_active());
+    }
+
+    #[test]
+    fn pan() {
+        let mut fingers = Fingers::new();
+
+        fingers
+            .touch(0, TouchPhase::Started, 0., 0., 0)
+            .touch(0, TouchPhase::Moved, 5., 0., 10)
+            .touch(0, TouchPhase::Moved, 20., 0., 20)
+            .touch(0, TouchPhase::Moved, 20., 10., 30)
+            .touch(0, TouchPhase::Ended, 20., 10., 40)
+            .update(1000);
+
+        // Movement within the tap slop does not start a pan yet.
+        assert_eq!(
+            fingers.poll(),
+            vec![
+                Gesture::Pan {
+                    phase: TouchPhase::Started,
+                    position: Vec2::new(20., 0.),
+                    delta: Vec2::new(15., 0.),
+                },
+                Gesture::Pan {
+                    phase: TouchPhase::Moved,
+                    position: Vec2::new(20., 10.),
+                    delta: Vec2::new(0., 10.),
+                },
+                Gesture::Pan {
+                    phase: TouchPhase::Ended,
+                    position: Vec2::new(20., 10.),
+                    delta: Vec2::ZERO,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn pinch() {
+        let mut fingers = Fingers::new();
+
+        fingers
+            .touch(0, TouchPhase::Started, 40., 50., 0)
+            .touch(1, TouchPhase::Started, 60., 50., 10)
+            .touch(1, TouchPhase::Moved, 80., 50., 20)
+            .touch(0, TouchPhase::Ended, 40., 50., 30)
+            .touch(1, TouchPhase::Ended, 80., 50., 40);
+
+        assert_eq!(
+            fingers.poll(),
+            vec![Gesture::Pinch {
+                center: Vec2::new(60., 50.),
+                scale: 2.,
+            }]
+        );
+    }
+
+    #[test]
+    fn rotate() {
+        let mut fingers = Fingers::new();
+
+        fingers
+            .touch(0, TouchPhase::Started, 50., 50., 0)
+            .touch(1, TouchPhase::Started, 60., 50., 10)
+            .touch(1, TouchPhase::Moved, 50., 60., 20);
+
+        // The distance between both fingers did not change, so there is no pinch.
+        let gestures = fingers.poll();
+        let [Gesture::Rotate { center, angle }] = gestures[..] else {
+            panic!("Expected a single rotation, got {gestures:?}");
+        };
+        assert_eq!(center, Vec2::new(50., 55.));
+        assert_near(angle, FRAC_PI_2);
+    }
+
+    #[test]
+    fn cancelled_touch_ends_everything() {
+        let mut fingers = Fingers::new();
+
+        fingers.touch(0, TouchPhase::Started, 0., 0., 0).touch(
+            0,
+            TouchPhase::Cancelled,
+            0.,
+            0.,
+            50,
+        );
+
+        assert_eq!(fingers.poll(), vec![]);
+        assert!(!fingers.gestures.is_active());
+
+        // A later tap is recognized as usual.
+        fingers
+            .touch(0, TouchPhase::Started, 0., 0., 100)
+            .touch(0, TouchPhase::Ended, 0., 0., 150);
+        assert_eq!(
+            fingers.poll(),
+            vec![Gesture::Tap {
+                position: Vec2::ZERO
+            }]
+        );
+    }
+}
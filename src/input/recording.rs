@@ -0,0 +1,360 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ErrorKind;
+
+use super::InputEvent;
+
+/// Identifies recording files and their format version.
+const MAGIC: [u8; 4] = *b"KFRC";
+const VERSION: u16 = 2;
+/// The maximum size of the header or a single frame, so that a corrupt length prefix cannot exhaust the memory.
+const MAX_ENTRY_SIZE: u64 = 16 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    magic: [u8; 4],
+    version: u16,
+}
+
+/// An event which is part of a [`Recording`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RecordedEvent {
+    Input(InputEvent),
+    /// The window was resized or moved to a display with a different scale factor. The size is given in physical pixels.
+    Resized {
+        width: u32,
+        height: u32,
+        scale_factor: f64,
+    },
+}
+
+impl From<InputEvent> for RecordedEvent {
+    fn from(event: InputEvent) -> Self {
+        Self::Input(event)
+    }
+}
+
+/// The events received before a frame, together with the delta of that frame.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RecordedFrame {
+    pub events: Vec<RecordedEvent>,
+    pub dt: f32,
+}
+
+/// A sequence of frames, each consisting of the input events received before it and its frame delta.
+/// Changes of the window size are recorded as well, so that a replay sees the same sizes as the recorded run.
+///
+/// Recordings are created with [`App::record`](crate::app::App::record) and can be replayed with
+/// [`App::replay`](crate::app::App::replay), or headless with [`testing::replay`](crate::testing::replay).
+/// As long as the event handler only depends on its input and the frame deltas, a replay reproduces the recorded run exactly.
+///
+/// # Example
+///
+/// ```
+/// use kaffee::prelude::*;
+///
+/// let mut recording = Recording::default();
+/// recording.push_frame(
+///     vec![InputEvent::MouseButton {
+///         button: MouseButton::Left,
+///         state: ElementState::Pressed,
+///     }],
+///     1. / 60.,
+/// );
+/// recording.push_frame(
+///     [RecordedEvent::Resized {
+///         width: 800,
+///         height: 600,
+///         scale_factor: 1.,
+///     }],
+///     1. / 60.,
+/// );
+///
+/// assert_eq!(recording.len(), 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    frames: Vec<RecordedFrame>,
+}
+
+impl Recording {
+    /// Loads a recording. If the file ends in the middle of a frame, e.g. because the recorded
+    /// application crashed, all complete frames are returned.
+    pub fn load<P>(path: P) -> Result<Self, ErrorKind>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|source| ErrorKind::io(path, source))?;
+        let mut reader = BufReader::new(file);
+
+        let header: Header = deserialize(&mut reader, path)?;
+        if header.magic != MAGIC || header.version != VERSION {
+            return Err(ErrorKind::parse(
+                Some(path.to_owned()),
+                "Not a recording or unsupported version",
+            ));
+        }
+
+        let mut frames = Vec::new();
+        loop {
+            match options().deserialize_from(&mut reader) {
+                Ok(frame) => frames.push(frame),
+                Err(e) => match *e {
+                    bincode::ErrorKind::Io(ref e)
+                        if e.kind() == std::io::ErrorKind::UnexpectedEof =>
+                    {
+                        break
+                    }
+                    e => return Err(ErrorKind::parse(Some(path.to_owned()), e)),
+                },
+            }
+        }
+
+        Ok(Self { frames })
+    }
+
+    pub fn save<P>(&self, path: P) -> Result<(), ErrorKind>
+    where
+        P: AsRef<Path>,
+    {
+        let mut recorder = Recorder::create(path)?;
+        for frame in &self.frames {
+            recorder.write_frame(frame)?;
+        }
+
+        Ok(())
+    }
+
+    /// Appends a frame which receives `events` and is then updated by `dt` seconds.
+    pub fn push_frame<E>(&mut self, events: impl IntoIterator<Item = E>, dt: f32)
+    where
+        E: Into<RecordedEvent>,
+    {
+        let events = events.into_iter().map(Into::into).collect();
+        self.frames.push(RecordedFrame { events, dt });
+    }
+
+    /// Returns the number of frames.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub(crate) fn into_frames(self) -> std::vec::IntoIter<RecordedFrame> {
+        self.frames.into_iter()
+    }
+}
+
+/// Writes frames to a recording file as they happen, so that the recording survives a crash.
+pub(crate) struct Recorder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    events: Vec<RecordedEvent>,
+}
+
+impl Recorder {
+    pub fn create<P>(path: P) -> Result<Self, ErrorKind>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_owned();
+        let file = File::create(&path).map_err(|source| ErrorKind::io(&path, source))?;
+        let mut writer = BufWriter::new(file);
+        serialize(
+            &mut writer,
+            &path,
+            &Header {
+                magic: MAGIC,
+                version: VERSION,
+            },
+        )?;
+
+        Ok(Self {
+            path,
+            writer,
+            events: Vec::new(),
+        })
+    }
+
+    /// Remembers an event for the next frame.
+    pub fn record_event(&mut self, event: &InputEvent) {
+        self.events.push(RecordedEvent::Input(event.clone()));
+    }
+
+    /// Remembers a change of the window size for the next frame.
+    pub fn record_resize(&mut self, width: u32, height: u32, scale_factor: f64) {
+        self.events.push(RecordedEvent::Resized {
+            width,
+            height,
+            scale_factor,
+        });
+    }
+
+    /// Writes a frame containing all events since the last frame.
+    pub fn record_frame(&mut self, dt: f32) -> Result<(), ErrorKind> {
+        let frame = RecordedFrame {
+            events: std::mem::take(&mut self.events),
+            dt,
+        };
+        self.write_frame(&frame)
+    }
+
+    fn write_frame(&mut self, frame: &RecordedFrame) -> Result<(), ErrorKind> {
+        serialize(&mut self.writer, &self.path, frame)?;
+        self.writer
+            .flush()
+            .map_err(|source| ErrorKind::io(&self.path, source))
+    }
+}
+
+fn serialize<T: Serialize>(writer: impl Write, path: &Path, value: &T) -> Result<(), ErrorKind> {
+    options()
+        .serialize_into(writer, value)
+        .map_err(|e| match *e {
+            bincode::ErrorKind::Io(source) => ErrorKind::io(path, source),
+            e => ErrorKind::parse(Some(path.to_owned()), e),
+        })
+}
+
+fn deserialize<T: for<'de> Deserialize<'de>>(
+    reader: impl Read,
+    path: &Path,
+) -> Result<T, ErrorKind> {
+    options()
+        .deserialize_from(reader)
+        .map_err(|e| ErrorKind::parse(Some(path.to_owned()), e))
+}
+
+/// The encoding of [`bincode::serialize_into`], with the size of each entry limited to [`MAX_ENTRY_SIZE`].
+fn options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .with_limit(MAX_ENTRY_SIZE)
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::event::{ElementState, MouseButton};
+
+    use super::*;
+
+    /// Returns a path in the temporary directory which is unique to the test.
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("kaffee-{}-{name}.rec", std::process::id()))
+    }
+
+    fn recording() -> Recording {
+        let mut recording = Recording::default();
+        recording.push_frame(
+            [InputEvent::MouseButton {
+                button: MouseButton::Left,
+                state: ElementState::Pressed,
+            }],
+            0.016,
+        );
+        recording.push_frame(
+            [RecordedEvent::Resized {
+                width: 800,
+                height: 600,
+                scale_factor: 1.5,
+            }],
+            0.02,
+        );
+        recording.push_frame(Vec::<InputEvent>::new(), 0.017);
+        recording
+    }
+
+    fn frames(recording: &Recording) -> String {
+        format!("{:?}", recording.frames)
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = temp_path("save-and-load");
+        let recording = recording();
+
+        recording.save(&path).unwrap();
+        let loaded = Recording::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(frames(&loaded), frames(&recording));
+    }
+
+    #[test]
+    fn truncated_file_keeps_complete_frames() {
+        let path = temp_path("truncated");
+        recording().save(&path).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 2]).unwrap();
+        let loaded = Recording::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(frames(&loaded), format!("{:?}", &recording().frames[..2]));
+    }
+
+    #[test]
+    fn bad_header() {
+        let path = temp_path("bad-header");
+
+        std::fs::write(&path, b"PNG\0\x01\0").unwrap();
+        let not_a_recording = Recording::load(&path);
+
+        let mut bytes = Vec::new();
+        options()
+            .serialize_into(
+                &mut bytes,
+                &Header {
+                    magic: MAGIC,
+                    version: VERSION + 1,
+                },
+            )
+            .unwrap();
+        std::fs::write(&path, bytes).unwrap();
+        let newer_version = Recording::load(&path);
+
+        std::fs::write(&path, b"KF").unwrap();
+        let too_short = Recording::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(not_a_recording, Err(ErrorKind::ParseError { .. })));
+        assert!(matches!(newer_version, Err(ErrorKind::ParseError { .. })));
+        assert!(matches!(too_short, Err(ErrorKind::ParseError { .. })));
+    }
+
+    #[test]
+    fn huge_length_prefix() {
+        let path = temp_path("huge-length-prefix");
+        let mut recording = recording();
+        recording.frames.truncate(1);
+        recording.save(&path).unwrap();
+
+        // A frame which claims to contain `u64::MAX` events, but ends right after the length.
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+        let truncated = Recording::load(&path);
+
+        // The same frame, but followed by enough data to exceed the size limit.
+        bytes.resize(bytes.len() + MAX_ENTRY_SIZE as usize, 0);
+        std::fs::write(&path, &bytes).unwrap();
+        let oversized = Recording::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(frames(&truncated.unwrap()), frames(&recording));
+        assert!(matches!(oversized, Err(ErrorKind::ParseError { .. })));
+    }
+}
@@ -0,0 +1,360 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use winit::event::{ElementState, MouseButton, VirtualKeyCode};
+
+use super::{
+    AxisBinding, Bindings, CursorPosition, GamepadAxis, GamepadButton, GamepadEvent, GamepadId,
+    InputEvent, KeyEvent,
+};
+
+/// A key, mouse button or gamepad button which can be queried through [`InputState`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Button {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+impl From<VirtualKeyCode> for Button {
+    fn from(key: VirtualKeyCode) -> Self {
+        Self::Key(key)
+    }
+}
+
+impl From<MouseButton> for Button {
+    fn from(button: MouseButton) -> Self {
+        Self::Mouse(button)
+    }
+}
+
+impl From<GamepadButton> for Button {
+    fn from(button: GamepadButton) -> Self {
+        Self::Gamepad(button)
+    }
+}
+
+/// Keeps track of which keys and mouse buttons are held down.
+///
+/// The state is updated from the input events of the application and can be queried at any time,
+/// e.g. through [`GfxContext::input`](crate::prelude::GfxContext::input) during `update`.
+/// "Just pressed" and "just released" refer to the time since the previous call to `update`.
+///
+/// Besides individual buttons, named actions and axes can be queried according to the current [`Bindings`].
+/// All connected gamepads are merged, i.e. a gamepad button is held down while it is held on any gamepad.
+///
+/// # Example
+///
+/// ```
+/// use kaffee::prelude::*;
+///
+/// let mut input = InputState::default();
+/// input.process(&InputEvent::MouseButton {
+///     button: MouseButton::Left,
+///     state: ElementState::Pressed,
+/// });
+///
+/// assert!(input.is_down(MouseButton::Left));
+/// assert!(input.just_pressed(MouseButton::Left));
+///
+/// input.advance();
+///
+/// assert!(input.is_down(MouseButton::Left));
+/// assert!(!input.just_pressed(MouseButton::Left));
+/// ```
+#[derive(Debug, Default)]
+pub struct InputState {
+    down: HashSet<Button>,
+    pressed: HashSet<Button>,
+    released: HashSet<Button>,
+    repeated: HashSet<Button>,
+    cursor: Option<CursorPosition>,
+    /// The connected gamepads and the buttons held down on each of them.
+    gamepads: HashMap<GamepadId, HashSet<GamepadButton>>,
+    gamepad_axes: HashMap<(GamepadId, GamepadAxis), f32>,
+    bindings: Bindings,
+}
+
+impl InputState {
+    /// Returns `true` while the button is held down.
+    pub fn is_down(&self, button: impl Into<Button>) -> bool {
+        self.down.contains(&button.into())
+    }
+
+    /// Returns `true` if the button was pressed since the last update.
+    pub fn just_pressed(&self, button: impl Into<Button>) -> bool {
+        self.pressed.contains(&button.into())
+    }
+
+    /// Returns `true` if the button was released since the last update.
+    pub fn just_released(&self, button: impl Into<Button>) -> bool {
+        self.released.contains(&button.into())
+    }
+
+    /// Returns `true` if the operating system sent a key repeat for a held key since the last update.
+    pub fn is_repeat(&self, button: impl Into<Button>) -> bool {
+        self.repeated.contains(&button.into())
+    }
+
+    /// Returns the last known cursor position, or `None` if the cursor is outside of the window.
+    pub fn cursor_position(&self) -> Option<CursorPosition> {
+        self.cursor
+    }
+
+    /// Returns the current value of a gamepad axis, or `0` if no gamepad is connected.
+    /// If multiple gamepads are connected, the value furthest from `0` is returned.
+    pub fn gamepad_axis(&self, axis: GamepadAxis) -> f32 {
+        self.gamepad_axes
+            .iter()
+            .filter(|((_, a), _)| *a == axis)
+            .map(|(_, value)| *value)
+            .fold(
+                0.,
+                |max, value| if value.abs() > max.abs() { value } else { max },
+            )
+    }
+
+    /// Returns the ids of all connected gamepads.
+    pub fn gamepads(&self) -> impl Iterator<Item = GamepadId> + '_ {
+        self.gamepads.keys().copied()
+    }
+
+    /// Returns `true` while any button bound to the action is held down.
+    pub fn action_down(&self, action: &str) -> bool {
+        self.bindings
+            .action(action)
+            .iter()
+            .any(|&button| self.is_down(button))
+    }
+
+    /// Returns `true` if any button bound to the action was pressed since the last update.
+    pub fn action_just_pressed(&self, action: &str) -> bool {
+        self.bindings
+            .action(action)
+            .iter()
+            .any(|&button| self.just_pressed(button))
+    }
+
+    /// Returns `true` if the action was released since the last update, i.e. one of its buttons
+    /// was released and none of the others is still held down.
+    pub fn action_just_released(&self, action: &str) -> bool {
+        let buttons = self.bindings.action(action);
+        buttons.iter().any(|&button| self.just_released(button)) && !self.action_down(action)
+    }
+
+    /// Returns the value of an axis in the range `-1..=1`. Unbound axes always return `0`.
+    pub fn axis(&self, axis: &str) -> f32 {
+        let value: f32 = self
+            .bindings
+            .axis(axis)
+            .iter()
+            .map(|binding| match *binding {
+                AxisBinding::Buttons { negative, positive } => {
+                    self.is_down(positive) as i32 as f32 - self.is_down(negative) as i32 as f32
+                }
+                AxisBinding::Gamepad(axis) => self.gamepad_axis(axis),
+            })
+            .sum();
+
+        value.clamp(-1., 1.)
+    }
+
+    pub fn bindings(&self) -> &Bindings {
+        &self.bindings
+    }
+
+    /// Returns the bindings mutably, e.g. to remap an action while the application is running.
+    pub fn bindings_mut(&mut self) -> &mut Bindings {
+        &mut self.bindings
+    }
+
+    /// Replaces all bindings, e.g. with ones loaded through [`Bindings::load`].
+    pub fn set_bindings(&mut self, bindings: Bindings) {
+        self.bindings = bindings;
+    }
+
+    /// Updates the state from an input event. This is done automatically by the application.
+    pub fn process(&mut self, event: &InputEvent) {
+        match *event {
+            InputEvent::Key(KeyEvent { state, key, .. }) => self.set(Button::Key(key), state),
+            InputEvent::MouseButton { button, state } => self.set(Button::Mouse(button), state),
+            InputEvent::Gamepad(event) => self.process_gamepad(event),
+            InputEvent::MouseMoved { position } => self.cursor = Some(position),
+            InputEvent::CursorLeft => self.cursor = None,
+            InputEvent::Focused(false) => {
+                // Release keys and mouse buttons, as the matching events will not arrive while the window is unfocused.
+                // Gamepad events keep arriving, so gamepad buttons stay in sync with the held buttons of each gamepad.
+                let released = &mut self.released;
+                self.down.retain(|&button| {
+                    let keep = matches!(button, Button::Gamepad(_));
+                    if !keep {
+                        released.insert(button);
+                    }
+                    keep
+                });
+            }
+            _ => (),
+        }
+    }
+
+    /// Starts a new update, clearing the "just pressed", "just released" and repeat states.
+    /// This is done automatically after every call to `update`.
+    pub fn advance(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        self.repeated.clear();
+    }
+
+    fn process_gamepad(&mut self, event: GamepadEvent) {
+        match event {
+            GamepadEvent::Connected(id) => {
+                self.gamepads.entry(id).or_default();
+            }
+            GamepadEvent::Disconnected(id) => {
+                self.gamepad_axes.retain(|(pad, _), _| *pad != id);
+                for button in self.gamepads.remove(&id).unwrap_or_default() {
+                    self.release_gamepad_button(button);
+                }
+            }
+            GamepadEvent::Button { id, button, state } => {
+                let held = self.gamepads.entry(id).or_default();
+                match state {
+                    // Gamepads do not repeat buttons, so a button held on another gamepad is neither pressed nor repeated.
+                    ElementState::Pressed => {
+                        if held.insert(button) && self.down.insert(Button::Gamepad(button)) {
+                            self.pressed.insert(Button::Gamepad(button));
+                        }
+                    }
+                    ElementState::Released => {
+                        if held.remove(&button) {
+                            self.release_gamepad_button(button);
+                        }
+                    }
+                }
+            }
+            GamepadEvent::Axis { id, axis, value } => {
+                self.gamepad_axes.insert((id, axis), value);
+            }
+        }
+    }
+
+    /// Releases a gamepad button, unless it is still held down on another gamepad.
+    fn release_gamepad_button(&mut self, button: GamepadButton) {
+        if !self.gamepads.values().any(|held| held.contains(&button)) {
+            self.set(Button::Gamepad(button), ElementState::Released);
+        }
+    }
+
+    fn set(&mut self, button: Button, state: ElementState) {
+        match state {
+            ElementState::Pressed => {
+                if self.down.insert(button) {
+                    self.pressed.insert(button);
+                } else {
+                    self.repeated.insert(button);
+                }
+            }
+            ElementState::Released => {
+                if self.down.remove(&button) {
+                    self.released.insert(button);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gamepad_button(id: usize, button: GamepadButton, state: ElementState) -> InputEvent {
+        InputEvent::Gamepad(GamepadEvent::Button {
+            id: GamepadId(id),
+            button,
+            state,
+        })
+    }
+
+    #[test]
+    fn buttons_are_merged_across_gamepads() {
+        let mut input = InputState::default();
+        let south = GamepadButton::South;
+
+        input.process(&gamepad_button(0, south, ElementState::Pressed));
+        input.advance();
+        input.process(&gamepad_button(1, south, ElementState::Pressed));
+
+        // Pressing a button which is already held on another gamepad is not a new press or a repeat.
+        assert!(input.is_down(south));
+        assert!(!input.just_pressed(south));
+        assert!(!input.is_repeat(south));
+
+        input.process(&gamepad_button(0, south, ElementState::Released));
+        assert!(input.is_down(south));
+        assert!(!input.just_released(south));
+
+        input.process(&gamepad_button(1, south, ElementState::Released));
+        assert!(!input.is_down(south));
+        assert!(input.just_released(south));
+    }
+
+    #[test]
+    fn disconnect_releases_buttons() {
+        let mut input = InputState::default();
+        let start = GamepadButton::Start;
+
+        input.process(&InputEvent::Gamepad(GamepadEvent::Connected(GamepadId(0))));
+        input.process(&gamepad_button(0, start, ElementState::Pressed));
+        input.process(&InputEvent::Gamepad(GamepadEvent::Disconnected(GamepadId(
+            0,
+        ))));
+
+        assert!(!input.is_down(start));
+        assert!(input.just_released(start));
+        assert_eq!(input.gamepads().count(), 0);
+    }
+
+    #[test]
+    fn gamepad_buttons_survive_focus_loss() {
+        let mut input = InputState::default();
+        let south = GamepadButton::South;
+
+        input.process(&InputEvent::Key(KeyEvent {
+            state: ElementState::Pressed,
+            key: VirtualKeyCode::A,
+            modifiers: Default::default(),
+        }));
+        input.process(&gamepad_button(0, south, ElementState::Pressed));
+        input.advance();
+
+        input.process(&InputEvent::Focused(false));
+        assert!(input.just_released(VirtualKeyCode::A));
+        assert!(input.is_down(south));
+
+        input.process(&gamepad_button(0, south, ElementState::Released));
+        input.advance();
+        input.process(&gamepad_button(0, south, ElementState::Pressed));
+
+        assert!(input.is_down(south));
+        assert!(input.just_pressed(south));
+    }
+
+    #[test]
+    fn key_repeat() {
+        let mut input = InputState::default();
+        let key = |state| {
+            InputEvent::Key(KeyEvent {
+                state,
+                key: VirtualKeyCode::A,
+                modifiers: Default::default(),
+            })
+        };
+
+        input.process(&key(ElementState::Pressed));
+        input.advance();
+        input.process(&key(ElementState::Pressed));
+
+        assert!(input.is_repeat(VirtualKeyCode::A));
+        assert!(!input.just_pressed(VirtualKeyCode::A));
+    }
+}
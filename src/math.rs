@@ -1,7 +1,35 @@
-use glam::Vec2;
+pub use glam::Vec2;
 
-#[derive(Debug, Clone, Copy)]
+/// An axis-aligned rectangle.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Rect {
     pub min: Vec2,
     pub max: Vec2,
 }
+
+impl Rect {
+    pub fn new(x: f32, y: f32, w: f32, h: f32) -> Self {
+        Self {
+            min: Vec2::new(x, y),
+            max: Vec2::new(x + w, y + h),
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max.y - self.min.y
+    }
+
+    /// Returns the area covered by both rectangles, or `None` if they do not overlap.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let rect = Rect {
+            min: self.min.max(other.min),
+            max: self.max.min(other.max),
+        };
+
+        (rect.width() > 0. && rect.height() > 0.).then_some(rect)
+    }
+}
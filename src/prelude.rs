@@ -2,9 +2,25 @@
 
 pub use crate::{
     app::App,
-    config::Config,
+    config::{Config, RedrawMode},
     error::ErrorKind,
     event::EventHandler,
-    gfx::{BatchExt, Color, FilterMode, GfxContext, PipelineExt, TextExt, TextureExt},
-    input::InputEvent,
+    gfx::{
+        AdapterInfo, Backend, BatchExt, BlendMode, Camera2D, Color, Features, FilterMode,
+        GfxContext, Limits, PipelineExt, PowerPreference, PresentMode, ShaderStage, TextExt,
+        TextureExt, TextureRef, WindowExt,
+    },
+    input::{
+        AxisBinding, Bindings, Button, CursorPosition, ElementState, GamepadAxis, GamepadBackend,
+        GamepadButton, GamepadEvent, GamepadId, Gesture, GestureConfig, GestureRecognizer,
+        ImeEvent, InputEvent, InputState, KeyEvent, ModifiersState, MouseButton, MouseScrollDelta,
+        RecordedEvent, Recording, TouchPhase, VirtualGamepads, VirtualKeyCode,
+    },
+    math::{Rect, Vec2},
+    scene::{Scene, SceneStack, Transition},
+    time::Timestep,
+    window::{CursorGrab, Fullscreen},
 };
+
+#[cfg(feature = "gilrs")]
+pub use crate::input::GilrsBackend;
@@ -0,0 +1,558 @@
+//! This module contains a stack of scenes, e.g. for switching between menus and gameplay.
+//!
+//! # Example
+//!
+//! The following example shows a game which can be paused. The game stays visible below the pause menu:
+//!
+//! ```no_run
+//! use kaffee::prelude::*;
+//!
+//! struct Game;
+//!
+//! impl Scene for Game {
+//!     fn input(&mut self, event: InputEvent) -> Transition {
+//!         if event.is_key_pressed(VirtualKeyCode::Escape) {
+//!             Transition::push(Pause)
+//!         } else {
+//!             Transition::None
+//!         }
+//!     }
+//!
+//!     fn update(&mut self, _: &mut GfxContext, _: f32) -> Transition {
+//!         Transition::None
+//!     }
+//!
+//!     fn redraw(&mut self, g: &mut GfxContext) {
+//!         g.draw_quad(100., 100., 200., Color::RED);
+//!     }
+//!
+//!     fn draw_when_covered(&self) -> bool {
+//!         true
+//!     }
+//! }
+//!
+//! struct Pause;
+//!
+//! impl Scene for Pause {
+//!     fn input(&mut self, event: InputEvent) -> Transition {
+//!         if event.is_key_pressed(VirtualKeyCode::Escape) {
+//!             Transition::Pop
+//!         } else {
+//!             Transition::None
+//!         }
+//!     }
+//!
+//!     fn update(&mut self, _: &mut GfxContext, _: f32) -> Transition {
+//!         Transition::None
+//!     }
+//!
+//!     fn redraw(&mut self, g: &mut GfxContext) {
+//!         g.draw_text(35., 35., 40., Color::WHITE, "Paused");
+//!     }
+//! }
+//!
+//! fn main() -> Result<(), ErrorKind> {
+//!     pollster::block_on(App::new(SceneStack::new(Game)))?.run()
+//! }
+//! ```
+
+use crate::{error::ErrorKind, event::EventHandler, gfx::GfxContext, input::InputEvent};
+
+/// A part of an application with its own input handling, update and drawing, e.g. a menu or a level.
+///
+/// Scenes are managed by a [`SceneStack`]. Only the scene on top of the stack receives input and is updated.
+/// The scenes below it are paused, but can keep drawing with [`Scene::draw_when_covered`].
+pub trait Scene {
+    /// Called when the scene is added to the stack.
+    fn init(&mut self, _g: &mut GfxContext) -> Result<(), ErrorKind> {
+        Ok(())
+    }
+
+    /// Receives input while the scene is on top of the stack. The returned transition is applied before the next update
+    /// or redraw, whichever comes first. Until then, no further input is passed to any scene, since it might be meant for the next one.
+    fn input(&mut self, _event: InputEvent) -> Transition {
+        Transition::None
+    }
+
+    /// Advances the scene by `dt` seconds while it is on top of the stack.
+    fn update(&mut self, g: &mut GfxContext, dt: f32) -> Transition;
+
+    fn redraw(&mut self, g: &mut GfxContext);
+
+    /// Returns `true` if the scene is still drawn while other scenes are on top of it, e.g. below a pause menu.
+    /// A scene is only drawn if all scenes above it are drawn as well.
+    fn draw_when_covered(&self) -> bool {
+        false
+    }
+
+    /// Called when another scene was pushed on top of this one.
+    fn covered(&mut self, _g: &mut GfxContext) {}
+
+    /// Called when this scene is on top of the stack again, after the scene above it was popped.
+    fn uncovered(&mut self, _g: &mut GfxContext) {}
+
+    /// Called when the scene is removed from the stack, including when the application exits.
+    fn removed(&mut self, _g: &mut GfxContext) {}
+
+    /// Called for every scene on the stack after the window was resized. `width` and `height` are given in logical pixels.
+    fn resize(&mut self, _g: &mut GfxContext, _width: f32, _height: f32) {}
+
+    /// Called for every scene on the stack when the window gained or lost keyboard focus. See [`EventHandler::focus_changed`].
+    fn focus_changed(&mut self, _g: &mut GfxContext, _focused: bool) {}
+
+    /// Called on the top scene when the user tries to close the window. See [`EventHandler::quit_requested`].
+    fn quit_requested(&mut self, _g: &mut GfxContext) -> bool {
+        true
+    }
+
+    /// Called for every scene on the stack when the application is suspended. See [`EventHandler::suspended`].
+    fn suspended(&mut self, _g: &mut GfxContext) {}
+
+    /// Called for every scene on the stack when the application is resumed. See [`EventHandler::resumed`].
+    fn resumed(&mut self, _g: &mut GfxContext) {}
+
+    /// Called for every scene on the stack after the graphics device was recreated. See [`EventHandler::device_restored`].
+    fn device_restored(&mut self, _g: &mut GfxContext) -> Result<(), ErrorKind> {
+        Ok(())
+    }
+}
+
+/// Changes the [`SceneStack`], as returned by [`Scene::input`] and [`Scene::update`].
+pub enum Transition {
+    /// Keeps the current scene.
+    None,
+    /// Pauses the current scene and puts a new scene on top of it.
+    Push(Box<dyn Scene>),
+    /// Removes the current scene and resumes the one below it. The application exits once the last scene was popped.
+    Pop,
+    /// Removes the current scene and puts a new scene in its place.
+    Replace(Box<dyn Scene>),
+}
+
+impl Transition {
+    pub fn push(scene: impl Scene + 'static) -> Self {
+        Self::Push(Box::new(scene))
+    }
+
+    pub fn replace(scene: impl Scene + 'static) -> Self {
+        Self::Replace(Box::new(scene))
+    }
+}
+
+/// An [`EventHandler`] which manages a stack of [`Scene`]s.
+///
+/// If a scene fails to initialize when it is pushed, the stack stays unchanged and the application stops with the error.
+pub struct SceneStack {
+    scenes: Vec<Box<dyn Scene>>,
+    /// The transition returned by [`Scene::input`], which is applied once a [`GfxContext`] is available.
+    pending: Option<Transition>,
+}
+
+impl SceneStack {
+    /// Creates a stack containing `scene`, which is initialized together with the application.
+    pub fn new(scene: impl Scene + 'static) -> Self {
+        Self {
+            scenes: vec![Box::new(scene)],
+            pending: None,
+        }
+    }
+
+    /// Returns the number of scenes on the stack.
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    fn apply_pending(&mut self, g: &mut GfxContext) {
+        if let Some(transition) = self.pending.take() {
+            self.apply(g, transition);
+        }
+    }
+
+    fn apply(&mut self, g: &mut GfxContext, transition: Transition) {
+        match transition {
+            Transition::None => (),
+            Transition::Push(mut scene) => {
+                if let Err(why) = scene.init(g) {
+                    g.fail(why);
+                    return;
+                }
+
+                if let Some(top) = self.scenes.last_mut() {
+                    top.covered(g);
+                }
+                self.scenes.push(scene);
+            }
+            Transition::Pop => {
+                if let Some(mut scene) = self.scenes.pop() {
+                    scene.removed(g);
+                }
+
+                match self.scenes.last_mut() {
+                    Some(top) => top.uncovered(g),
+                    None => g.quit(),
+                }
+            }
+            Transition::Replace(mut scene) => {
+                if let Err(why) = scene.init(g) {
+                    g.fail(why);
+                    return;
+                }
+
+                if let Some(mut previous) = self.scenes.pop() {
+                    previous.removed(g);
+                }
+                self.scenes.push(scene);
+            }
+        }
+    }
+}
+
+impl EventHandler for SceneStack {
+    fn init(&mut self, g: &mut GfxContext) -> Result<(), ErrorKind> {
+        for scene in &mut self.scenes {
+            scene.init(g)?;
+        }
+
+        Ok(())
+    }
+
+    fn input(&mut self, event: InputEvent) {
+        if self.pending.is_some() {
+            return;
+        }
+
+        if let Some(top) = self.scenes.last_mut() {
+            let transition = top.input(event);
+            if !matches!(transition, Transition::None) {
+                self.pending = Some(transition);
+            }
+        }
+    }
+
+    fn update(&mut self, g: &mut GfxContext, dt: f32) {
+        self.apply_pending(g);
+
+        if let Some(top) = self.scenes.last_mut() {
+            let transition = top.update(g, dt);
+            self.apply(g, transition);
+        }
+    }
+
+    fn redraw(&mut self, g: &mut GfxContext) {
+        // A fixed timestep may skip the update in some frames, which must not delay the transition.
+        self.apply_pending(g);
+
+        // The top scene is always drawn, the ones below only as long as they are drawn when covered.
+        let visible = self
+            .scenes
+            .iter()
+            .rev()
+            .skip(1)
+            .take_while(|scene| scene.draw_when_covered())
+            .count()
+            + 1;
+        let first = self.scenes.len().saturating_sub(visible);
+
+        for scene in &mut self.scenes[first..] {
+            scene.redraw(g);
+        }
+    }
+
+    fn resize(&mut self, g: &mut GfxContext, width: f32, height: f32) {
+        for scene in &mut self.scenes {
+            scene.resize(g, width, height);
+        }
+    }
+
+    fn focus_changed(&mut self, g: &mut GfxContext, focused: bool) {
+        for scene in &mut self.scenes {
+            scene.focus_changed(g, focused);
+        }
+    }
+
+    fn quit_requested(&mut self, g: &mut GfxContext) -> bool {
+        match self.scenes.last_mut() {
+            Some(top) => top.quit_requested(g),
+            None => true,
+        }
+    }
+
+    fn suspended(&mut self, g: &mut GfxContext) {
+        for scene in &mut self.scenes {
+            scene.suspended(g);
+        }
+    }
+
+    fn resumed(&mut self, g: &mut GfxContext) {
+        for scene in &mut self.scenes {
+            scene.resumed(g);
+        }
+    }
+
+    fn shutdown(&mut self, g: &mut GfxContext) {
+        while let Some(mut scene) = self.scenes.pop() {
+            scene.removed(g);
+        }
+    }
+
+    fn device_restored(&mut self, g: &mut GfxContext) -> Result<(), ErrorKind> {
+        for scene in &mut self.scenes {
+            scene.device_restored(g)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        testing::with_headless,
+        time::{Stepper, Timestep},
+    };
+
+    use super::*;
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    /// A scene which logs its callbacks and returns `next` from its next call to `input` or `update`.
+    struct Probe {
+        name: &'static str,
+        log: Log,
+        next: Option<Transition>,
+        draw_when_covered: bool,
+        fail_init: bool,
+    }
+
+    impl Probe {
+        fn new(name: &'static str, log: &Log) -> Self {
+            Self {
+                name,
+                log: Rc::clone(log),
+                next: None,
+                draw_when_covered: false,
+                fail_init: false,
+            }
+        }
+
+        fn then(self, next: Transition) -> Self {
+            Self {
+                next: Some(next),
+                ..self
+            }
+        }
+
+        fn record(&self, event: &str) {
+            self.log.borrow_mut().push(format!("{} {event}", self.name));
+        }
+
+        fn next(&mut self) -> Transition {
+            self.next.take().unwrap_or(Transition::None)
+        }
+    }
+
+    impl Scene for Probe {
+        fn init(&mut self, _: &mut GfxContext) -> Result<(), ErrorKind> {
+            self.record("init");
+            if self.fail_init {
+                return Err(ErrorKind::IoError {
+                    path: None,
+                    source: std::io::Error::other("scene failed"),
+                });
+            }
+            Ok(())
+        }
+
+        fn input(&mut self, _: InputEvent) -> Transition {
+            self.record("input");
+            self.next()
+        }
+
+        fn update(&mut self, _: &mut GfxContext, _: f32) -> Transition {
+            self.record("update");
+            self.next()
+        }
+
+        fn redraw(&mut self, _: &mut GfxContext) {
+            self.record("redraw");
+        }
+
+        fn draw_when_covered(&self) -> bool {
+            self.draw_when_covered
+        }
+
+        fn covered(&mut self, _: &mut GfxContext) {
+            self.record("covered");
+        }
+
+        fn uncovered(&mut self, _: &mut GfxContext) {
+            self.record("uncovered");
+        }
+
+        fn removed(&mut self, _: &mut GfxContext) {
+            self.record("removed");
+        }
+
+        fn focus_changed(&mut self, _: &mut GfxContext, focused: bool) {
+            self.record(&format!("focused {focused}"));
+        }
+
+        fn suspended(&mut self, _: &mut GfxContext) {
+            self.record("suspended");
+        }
+
+        fn resumed(&mut self, _: &mut GfxContext) {
+            self.record("resumed");
+        }
+    }
+
+    fn take(log: &Log) -> Vec<String> {
+        std::mem::take(&mut *log.borrow_mut())
+    }
+
+    #[test]
+    fn push_replace_pop() {
+        let log = Log::default();
+        let c = Probe::new("c", &log).then(Transition::Pop);
+        let b = Probe::new("b", &log).then(Transition::replace(c));
+        let a = Probe::new("a", &log).then(Transition::push(b));
+        let mut stack = SceneStack::new(a);
+
+        with_headless(|g| {
+            stack.init(g).unwrap();
+            assert_eq!(take(&log), ["a init"]);
+
+            stack.update(g, 0.);
+            assert_eq!(take(&log), ["a update", "b init", "a covered"]);
+            assert_eq!(stack.len(), 2);
+
+            stack.update(g, 0.);
+            assert_eq!(take(&log), ["b update", "c init", "b removed"]);
+            assert_eq!(stack.len(), 2);
+
+            stack.update(g, 0.);
+            assert_eq!(take(&log), ["c update", "c removed", "a uncovered"]);
+            assert_eq!(stack.len(), 1);
+            assert!(!g.should_quit());
+
+            stack.apply(g, Transition::Pop);
+            assert_eq!(take(&log), ["a removed"]);
+            assert!(stack.is_empty());
+            assert!(g.should_quit());
+        });
+    }
+
+    #[test]
+    fn input_waits_for_pending_transition() {
+        let log = Log::default();
+        let pause = Probe::new("pause", &log).then(Transition::Pop);
+        let game = Probe::new("game", &log).then(Transition::push(pause));
+        let mut stack = SceneStack::new(game);
+
+        with_headless(|g| {
+            stack.init(g).unwrap();
+            take(&log);
+
+            // The second event would reach the game again, which pushed the pause menu already.
+            stack.input(InputEvent::CursorLeft);
+            stack.input(InputEvent::CursorLeft);
+            assert_eq!(take(&log), ["game input"]);
+
+            // The transition is applied even if a fixed timestep skips the update.
+            stack.redraw(g);
+            assert_eq!(take(&log), ["pause init", "game covered", "pause redraw"]);
+
+            stack.input(InputEvent::CursorLeft);
+            stack.update(g, 0.);
+            assert_eq!(
+                take(&log),
+                [
+                    "pause input",
+                    "pause removed",
+                    "game uncovered",
+                    "game update"
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn only_scenes_drawn_when_covered_are_visible() {
+        let log = Log::default();
+        let scene = |name, draw_when_covered| Probe {
+            draw_when_covered,
+            ..Probe::new(name, &log)
+        };
+        let mut stack = SceneStack::new(scene("a", true));
+
+        with_headless(|g| {
+            stack.init(g).unwrap();
+            stack.apply(g, Transition::push(scene("b", false)));
+            stack.apply(g, Transition::push(scene("c", true)));
+            stack.apply(g, Transition::push(scene("d", false)));
+            take(&log);
+
+            // `a` is drawn when covered, but hidden by `b`.
+            stack.redraw(g);
+            assert_eq!(take(&log), ["c redraw", "d redraw"]);
+        });
+    }
+
+    #[test]
+    fn init_error_stops_the_application() {
+        let log = Log::default();
+        let broken = Probe {
+            fail_init: true,
+            ..Probe::new("broken", &log)
+        };
+        let mut stack = SceneStack::new(Probe::new("a", &log).then(Transition::replace(broken)));
+
+        with_headless(|g| {
+            stack.init(g).unwrap();
+            take(&log);
+
+            let mut stepper = Stepper::new(Timestep::Variable).unwrap();
+            assert!(matches!(
+                stepper.frame(&mut stack, g, 0.),
+                Err(ErrorKind::IoError { path: None, .. })
+            ));
+
+            // The stack is left unchanged.
+            assert_eq!(take(&log), ["a update", "broken init", "a redraw"]);
+            assert_eq!(stack.len(), 1);
+        });
+    }
+
+    #[test]
+    fn hooks_reach_every_scene() {
+        let log = Log::default();
+        let mut stack = SceneStack::new(Probe::new("a", &log));
+
+        with_headless(|g| {
+            stack.init(g).unwrap();
+            stack.apply(g, Transition::push(Probe::new("b", &log)));
+            take(&log);
+
+            stack.focus_changed(g, false);
+            stack.suspended(g);
+            stack.resumed(g);
+            assert_eq!(
+                take(&log),
+                [
+                    "a focused false",
+                    "b focused false",
+                    "a suspended",
+                    "b suspended",
+                    "a resumed",
+                    "b resumed"
+                ]
+            );
+        });
+    }
+}
@@ -0,0 +1,286 @@
+//! This module contains helpers for golden-image regression tests.
+//!
+//! An [`EventHandler`] is run for a number of frames, or through a [`Recording`] of a previous run,
+//! on a headless [`GfxContext`] and the last frame is compared against a reference PNG. References are only written when the `KAFFEE_BLESS`
+//! environment variable is set, e.g. `KAFFEE_BLESS=1 cargo test`. A missing reference fails the test.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use kaffee::{prelude::*, testing};
+//!
+//! struct Scene;
+//!
+//! impl EventHandler for Scene {
+//!     fn init(&mut self, _: &mut GfxContext) -> Result<(), ErrorKind> {
+//!         Ok(())
+//!     }
+//!
+//!     fn input(&mut self, _: InputEvent) {}
+//!     fn update(&mut self, _: &mut GfxContext, _: f32) {}
+//!
+//!     fn redraw(&mut self, g: &mut GfxContext) {
+//!         g.clear_color(Color::BLACK);
+//!         g.draw_rectangle(10., 10., 40., 20., Color::RED);
+//!     }
+//! }
+//!
+//! // In an integration test, e.g. `tests/golden.rs`:
+//! #[test]
+//! fn draw_rectangle() {
+//!     let config = Config {
+//!         width: 64,
+//!         height: 64,
+//!         force_fallback_adapter: true,
+//!         ..Config::default()
+//!     };
+//!
+//!     let frame = pollster::block_on(testing::run_frames(&mut Scene, &config, 1)).unwrap();
+//!     testing::assert_golden(&frame, "tests/golden/draw_rectangle.png", 2);
+//! }
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use image::{Rgba, RgbaImage};
+
+use crate::{
+    config::Config,
+    error::ErrorKind,
+    event::{self, EventHandler},
+    gfx::GfxContext,
+    input::{RecordedEvent, Recording},
+    time::Stepper,
+};
+
+/// The delta passed to `update` for every frame, so that test runs are deterministic.
+pub const FRAME_DT: f32 = 1. / 60.;
+
+/// Runs `handler` for `frames` frames on a headless [`GfxContext`] of `config.width` x `config.height` pixels
+/// and returns the last frame.
+pub async fn run_frames<H: EventHandler>(
+    handler: &mut H,
+    config: &Config,
+    frames: u32,
+) -> Result<RgbaImage, ErrorKind> {
+    let mut g = GfxContext::new_headless(config).await?;
+    let mut stepper = Stepper::new(config.timestep)?;
+
+    handler.init(&mut g)?;
+
+    for _ in 0..frames {
+        stepper.frame(handler, &mut g, FRAME_DT)?;
+    }
+
+    g.read_frame()
+}
+
+/// Replays `recording` through `handler` on a headless [`GfxContext`] and returns the last frame.
+/// Unlike [`run_frames`], every frame uses the recorded delta, and the context is resized like the recorded window.
+pub async fn replay<H: EventHandler>(
+    handler: &mut H,
+    config: &Config,
+    recording: Recording,
+) -> Result<RgbaImage, ErrorKind> {
+    let mut g = GfxContext::new_headless(config).await?;
+    let mut stepper = Stepper::new(config.timestep)?;
+
+    handler.init(&mut g)?;
+
+    for frame in recording.into_frames() {
+        for event in frame.events {
+            match event {
+                RecordedEvent::Input(event) => event::dispatch(handler, &mut g, event),
+                RecordedEvent::Resized {
+                    width,
+                    height,
+                    scale_factor,
+                } => event::resize(handler, &mut g, width, height, scale_factor),
+            }
+        }
+        stepper.frame(handler, &mut g, frame.dt)?;
+    }
+
+    g.read_frame()
+}
+
+/// Compares two images. A pixel matches if none of its channels differ by more than `tolerance`.
+///
+/// Returns the number of mismatching pixels together with a diff image, in which mismatching pixels
+/// are highlighted in red, or `None` if the images match.
+pub fn compare(
+    actual: &RgbaImage,
+    expected: &RgbaImage,
+    tolerance: u8,
+) -> Option<(usize, RgbaImage)> {
+    if actual.dimensions() != expected.dimensions() {
+        let (width, height) = actual.dimensions();
+        let diff = RgbaImage::from_pixel(width, height, Rgba([255, 0, 0, 255]));
+        return Some(((width * height) as usize, diff));
+    }
+
+    let mut mismatches = 0;
+    let diff = RgbaImage::from_fn(actual.width(), actual.height(), |x, y| {
+        let a = actual.get_pixel(x, y);
+        let e = expected.get_pixel(x, y);
+
+        let matches =
+            a.0.iter()
+                .zip(e.0.iter())
+                .all(|(a, e)| a.abs_diff(*e) <= tolerance);
+
+        if matches {
+            // Dim matching pixels so that the mismatches stand out.
+            let luma = (e[0] as u32 + e[1] as u32 + e[2] as u32) / 3 / 4;
+            Rgba([luma as u8, luma as u8, luma as u8, 255])
+        } else {
+            mismatches += 1;
+            Rgba([255, 0, 0, 255])
+        }
+    });
+
+    (mismatches > 0).then_some((mismatches, diff))
+}
+
+/// Asserts that `actual` matches the reference image at `reference` within the given per-channel `tolerance`.
+///
+/// On failure, `<name>.actual.png` and `<name>.diff.png` are written next to the reference before panicking.
+/// If the `KAFFEE_BLESS` environment variable is set, the reference is written instead.
+///
+/// # Panics
+///
+/// Panics if the images differ or the reference does not exist.
+pub fn assert_golden<P: AsRef<Path>>(actual: &RgbaImage, reference: P, tolerance: u8) {
+    let reference = reference.as_ref();
+
+    if std::env::var_os("KAFFEE_BLESS").is_some() {
+        if let Some(parent) = reference.parent() {
+            std::fs::create_dir_all(parent).expect("Failed to create reference directory");
+        }
+        actual
+            .save(reference)
+            .expect("Failed to write reference image");
+        log::info!("Wrote reference image {}", reference.display());
+        return;
+    }
+
+    if !reference.exists() {
+        let actual_path = sibling(reference, "actual");
+        actual
+            .save(&actual_path)
+            .expect("Failed to write actual image");
+
+        panic!(
+            "Reference image {} does not exist (see {}), run with KAFFEE_BLESS=1 to create it",
+            reference.display(),
+            actual_path.display()
+        );
+    }
+
+    let expected = image::open(reference)
+        .expect("Failed to load reference image")
+        .to_rgba8();
+
+    if let Some((mismatches, diff)) = compare(actual, &expected, tolerance) {
+        let actual_path = sibling(reference, "actual");
+        let diff_path = sibling(reference, "diff");
+
+        actual
+            .save(&actual_path)
+            .expect("Failed to write actual image");
+        diff.save(&diff_path).expect("Failed to write diff image");
+
+        panic!(
+            "{} pixels differ from {} (see {} and {})",
+            mismatches,
+            reference.display(),
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+/// Turns `dir/name.png` into `dir/name.<suffix>.png`.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{stem}.{suffix}.png"))
+}
+
+/// Runs `f` with a small headless [`GfxContext`] on a software adapter.
+/// Contexts are created one at a time, since some drivers do not support multiple instances at once.
+#[cfg(test)]
+pub(crate) fn with_headless<T>(f: impl FnOnce(&mut GfxContext) -> T) -> T {
+    static GPU: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    let _gpu = GPU.lock().unwrap_or_else(|e| e.into_inner());
+    let config = Config {
+        width: 64,
+        height: 64,
+        force_fallback_adapter: true,
+        ..Config::default()
+    };
+    let mut g = pollster::block_on(GfxContext::new_headless(&config)).unwrap();
+    f(&mut g)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+    fn image(pixels: [[u8; 4]; 4]) -> RgbaImage {
+        RgbaImage::from_fn(2, 2, |x, y| Rgba(pixels[(y * 2 + x) as usize]))
+    }
+
+    #[test]
+    fn equal_images_match() {
+        let a = image([[10, 20, 30, 255]; 4]);
+
+        assert!(compare(&a, &a.clone(), 0).is_none());
+    }
+
+    #[test]
+    fn differences_within_tolerance_match() {
+        let a = image([[10, 20, 30, 255]; 4]);
+        let b = image([[12, 18, 30, 253]; 4]);
+
+        assert!(compare(&a, &b, 2).is_none());
+        assert_eq!(compare(&a, &b, 1).map(|(n, _)| n), Some(4));
+    }
+
+    #[test]
+    fn diff_highlights_mismatches() {
+        let expected = image([[40, 80, 120, 255]; 4]);
+        let mut actual = expected.clone();
+        actual.put_pixel(1, 0, Rgba([200, 80, 120, 255]));
+
+        let (mismatches, diff) = compare(&actual, &expected, 0).unwrap();
+
+        assert_eq!(mismatches, 1);
+        assert_eq!(*diff.get_pixel(1, 0), RED);
+        // Matching pixels are dimmed to a quarter of their brightness.
+        assert_eq!(*diff.get_pixel(0, 0), Rgba([20, 20, 20, 255]));
+        assert_eq!(*diff.get_pixel(1, 1), Rgba([20, 20, 20, 255]));
+    }
+
+    #[test]
+    fn size_mismatch_fails_everywhere() {
+        let actual = RgbaImage::new(3, 2);
+        let expected = RgbaImage::new(2, 2);
+
+        let (mismatches, diff) = compare(&actual, &expected, 255).unwrap();
+
+        assert_eq!(mismatches, 6);
+        assert_eq!(diff.dimensions(), (3, 2));
+        assert!(diff.pixels().all(|pixel| *pixel == RED));
+    }
+
+    #[test]
+    fn sibling_paths() {
+        assert_eq!(
+            sibling(Path::new("tests/golden/text.png"), "diff"),
+            Path::new("tests/golden/text.diff.png")
+        );
+    }
+}
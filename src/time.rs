@@ -0,0 +1,234 @@
+//! This module contains types for measuring frame time and stepping the simulation.
+
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::ErrorKind,
+    event::EventHandler,
+    gfx::{BatchExt, GfxContext},
+};
+
+/// Determines how often [`EventHandler::update`] is called per frame.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Timestep {
+    /// `update` is called once per frame with the real elapsed time.
+    #[default]
+    Variable,
+    /// `update` is called with a constant delta as often as needed to catch up with real time.
+    Fixed {
+        /// The delta passed to `update`, in seconds.
+        dt: f32,
+        /// The maximum number of `update` calls per frame. Any remaining time is dropped.
+        max_steps: u32,
+    },
+}
+
+impl Timestep {
+    /// Creates a fixed timestep running at the given rate, allowing up to 8 steps per frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hz` is `0`.
+    pub fn fixed(hz: u32) -> Self {
+        assert!(hz > 0, "The rate of a fixed timestep has to be positive");
+
+        Self::Fixed {
+            dt: 1. / hz as f32,
+            max_steps: 8,
+        }
+    }
+
+    /// Checks that a fixed timestep has a positive delta and allows at least one step per frame.
+    fn validate(self) -> Result<Self, ErrorKind> {
+        match self {
+            Self::Fixed { dt, .. } if !(dt > 0. && dt.is_finite()) => {
+                Err(ErrorKind::InvalidConfig(format!(
+                    "The delta of a fixed timestep has to be positive, got {dt}"
+                )))
+            }
+            Self::Fixed { max_steps: 0, .. } => Err(ErrorKind::InvalidConfig(
+                "A fixed timestep has to allow at least one step per frame".into(),
+            )),
+            timestep => Ok(timestep),
+        }
+    }
+}
+
+/// Measures the real time elapsed between frames.
+pub(crate) struct Clock {
+    last: Instant,
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Self {
+            last: Instant::now(),
+        }
+    }
+
+    /// Restarts the measurement, so that time spent idle is not reported by the next [`Clock::tick`].
+    pub fn reset(&mut self) {
+        self.last = Instant::now();
+    }
+
+    /// Returns the seconds elapsed since the last call.
+    pub fn tick(&mut self) -> f32 {
+        let now = Instant::now();
+        let dt = now.duration_since(self.last).as_secs_f32();
+        self.last = now;
+        dt
+    }
+}
+
+/// Calls [`EventHandler::update`] according to a [`Timestep`].
+pub(crate) struct Stepper {
+    timestep: Timestep,
+    accumulator: f32,
+}
+
+impl Stepper {
+    /// Fails if `timestep` is a [`Timestep::Fixed`] with a delta which is not positive.
+    pub fn new(timestep: Timestep) -> Result<Self, ErrorKind> {
+        Ok(Self {
+            timestep: timestep.validate()?,
+            accumulator: 0.,
+        })
+    }
+
+    /// Runs a single frame: updates the handler by `dt` seconds, then redraws it.
+    /// Errors the handler reported through [`GfxContext::fail`] are returned once the frame ended.
+    pub fn frame<H: EventHandler>(
+        &mut self,
+        handler: &mut H,
+        g: &mut GfxContext,
+        dt: f32,
+    ) -> Result<(), ErrorKind> {
+        if g.is_device_lost() {
+            // Frames are skipped until the new device is available.
+            if !g.recreate()? {
+                g.request_redraw();
+                return Ok(());
+            }
+            handler.device_restored(g)?;
+        }
+
+        let alpha = self.step(handler, g, dt);
+        g.set_alpha(alpha);
+        handler.redraw(g);
+        g.end_frame()?;
+
+        match g.take_handler_error() {
+            Some(why) => Err(why),
+            None => Ok(()),
+        }
+    }
+
+    /// Advances the simulation by `dt` seconds and returns the interpolation alpha,
+    /// i.e. how far the current time lies between the last and the next fixed step.
+    pub fn step<H: EventHandler>(&mut self, handler: &mut H, g: &mut GfxContext, dt: f32) -> f32 {
+        self.advance(dt, |dt| update(handler, g, dt))
+    }
+
+    /// Calls `update` as often as the [`Timestep`] requires for `dt` seconds and returns the interpolation alpha.
+    fn advance(&mut self, dt: f32, mut update: impl FnMut(f32)) -> f32 {
+        match self.timestep {
+            Timestep::Variable => {
+                update(dt);
+                1.
+            }
+            Timestep::Fixed {
+                dt: step,
+                max_steps,
+            } => {
+                self.accumulator += dt;
+
+                let mut steps = 0;
+                while self.accumulator >= step && steps < max_steps {
+                    update(step);
+                    self.accumulator -= step;
+                    steps += 1;
+                }
+
+                if steps == max_steps {
+                    self.accumulator = self.accumulator.min(step);
+                }
+
+                self.accumulator / step
+            }
+        }
+    }
+}
+
+/// Calls [`EventHandler::update`], then starts a new update for the input state,
+/// so that presses are only reported to the first update after they happened.
+fn update<H: EventHandler>(handler: &mut H, g: &mut GfxContext, dt: f32) {
+    handler.update(g, dt);
+    g.input_mut().advance();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advance(stepper: &mut Stepper, dt: f32) -> (Vec<f32>, f32) {
+        let mut steps = Vec::new();
+        let alpha = stepper.advance(dt, |dt| steps.push(dt));
+        (steps, alpha)
+    }
+
+    #[test]
+    fn variable_timestep_updates_once() {
+        let mut stepper = Stepper::new(Timestep::Variable).unwrap();
+
+        assert_eq!(advance(&mut stepper, 0.25), (vec![0.25], 1.));
+    }
+
+    #[test]
+    fn fixed_timestep_accumulates() {
+        let mut stepper = Stepper::new(Timestep::Fixed {
+            dt: 0.25,
+            max_steps: 8,
+        })
+        .unwrap();
+
+        assert_eq!(advance(&mut stepper, 0.125), (vec![], 0.5));
+        assert_eq!(advance(&mut stepper, 0.5), (vec![0.25, 0.25], 0.5));
+        assert_eq!(advance(&mut stepper, 0.125), (vec![0.25], 0.));
+    }
+
+    #[test]
+    fn fixed_timestep_caps_catch_up() {
+        let mut stepper = Stepper::new(Timestep::Fixed {
+            dt: 0.25,
+            max_steps: 2,
+        })
+        .unwrap();
+
+        // The remaining 1.5 seconds are dropped, except for at most one step.
+        let (steps, alpha) = advance(&mut stepper, 2.);
+        assert_eq!(steps, vec![0.25, 0.25]);
+        assert_eq!(alpha, 1.);
+
+        assert_eq!(advance(&mut stepper, 0.).0.len(), 1);
+    }
+
+    #[test]
+    fn invalid_fixed_timestep() {
+        for dt in [0., -1., f32::NAN, f32::INFINITY] {
+            assert!(Stepper::new(Timestep::Fixed { dt, max_steps: 8 }).is_err());
+        }
+        assert!(Stepper::new(Timestep::Fixed {
+            dt: 0.25,
+            max_steps: 0
+        })
+        .is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn fixed_zero_hz() {
+        Timestep::fixed(0);
+    }
+}
@@ -0,0 +1,196 @@
+use std::cmp::Reverse;
+
+use serde::{Deserialize, Serialize};
+use winit::{
+    dpi::{LogicalSize, PhysicalSize},
+    event_loop::EventLoop,
+    monitor::MonitorHandle,
+    window::{CursorGrabMode, Icon, Window, WindowBuilder, WindowLevel},
+};
+
+use crate::{config::Config, error::ErrorKind, fs};
+
+/// Determines whether the window covers the whole screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Fullscreen {
+    #[default]
+    Windowed,
+    /// Covers the current monitor with a borderless window, without changing its video mode.
+    Borderless,
+    /// Switches the current monitor to the smallest video mode which fits the configured window size,
+    /// preferring higher refresh rates. If no video mode is large enough, the largest one is used.
+    /// Falls back to [`Fullscreen::Borderless`] if no video mode is available.
+    Exclusive,
+}
+
+/// Determines whether the cursor is kept inside the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CursorGrab {
+    #[default]
+    None,
+    /// The cursor cannot leave the window.
+    Confined,
+    /// The cursor cannot move at all, which is useful for mouse look. Mouse movement is still reported.
+    Locked,
+}
+
+/// Creates the window described by `config`.
+pub(crate) fn build(config: &Config, event_loop: &EventLoop<()>) -> Result<Window, ErrorKind> {
+    let icon = match &config.icon {
+        Some(path) => {
+            let image = fs::load_image(path)?;
+            let (width, height) = image.dimensions();
+            let icon = Icon::from_rgba(image.into_raw(), width, height)
+                .map_err(|e| ErrorKind::parse(Some(path.clone()), e))?;
+            Some(icon)
+        }
+        None => None,
+    };
+
+    let window_level = if config.always_on_top {
+        WindowLevel::AlwaysOnTop
+    } else {
+        WindowLevel::Normal
+    };
+
+    let monitor = event_loop
+        .primary_monitor()
+        .or_else(|| event_loop.available_monitors().next());
+
+    let size = LogicalSize::new(config.width, config.height);
+    let mut builder = WindowBuilder::new()
+        .with_title(&config.title)
+        .with_inner_size(size)
+        .with_resizable(config.resizable)
+        .with_fullscreen(to_winit(config.fullscreen, monitor, size))
+        .with_maximized(config.maximized)
+        .with_decorations(config.decorations)
+        .with_transparent(config.transparent)
+        .with_window_level(window_level)
+        .with_window_icon(icon);
+
+    if let Some((width, height)) = config.min_size {
+        builder = builder.with_min_inner_size(LogicalSize::new(width, height));
+    }
+    if let Some((width, height)) = config.max_size {
+        builder = builder.with_max_inner_size(LogicalSize::new(width, height));
+    }
+
+    builder.build(event_loop).map_err(ErrorKind::WindowError)
+}
+
+/// Converts the fullscreen mode for `monitor`, which is the monitor the window is (or will be) shown on.
+/// `size` is the window size the video mode of [`Fullscreen::Exclusive`] should match.
+pub(crate) fn to_winit(
+    fullscreen: Fullscreen,
+    monitor: Option<MonitorHandle>,
+    size: LogicalSize<u16>,
+) -> Option<winit::window::Fullscreen> {
+    match fullscreen {
+        Fullscreen::Windowed => None,
+        Fullscreen::Borderless => Some(winit::window::Fullscreen::Borderless(monitor)),
+        Fullscreen::Exclusive => {
+            let video_mode = monitor.as_ref().and_then(|monitor| {
+                let size = size.to_physical(monitor.scale_factor());
+                best_video_mode(monitor.video_modes(), size, |mode| {
+                    (mode.size(), mode.refresh_rate_millihertz())
+                })
+            });
+
+            match video_mode {
+                Some(video_mode) => Some(winit::window::Fullscreen::Exclusive(video_mode)),
+                None => {
+                    log::warn!("No video mode available, using borderless fullscreen");
+                    Some(winit::window::Fullscreen::Borderless(monitor))
+                }
+            }
+        }
+    }
+}
+
+/// Picks the smallest mode which is at least as large as `size`, preferring higher refresh rates,
+/// or the largest mode if none is large enough. `describe` returns the size and refresh rate of a mode.
+fn best_video_mode<M>(
+    modes: impl IntoIterator<Item = M>,
+    size: PhysicalSize<u32>,
+    describe: impl Fn(&M) -> (PhysicalSize<u32>, u32),
+) -> Option<M> {
+    let area = |size: PhysicalSize<u32>| size.width as u64 * size.height as u64;
+
+    let (fitting, smaller): (Vec<M>, Vec<M>) = modes.into_iter().partition(|mode| {
+        let (mode_size, _) = describe(mode);
+        mode_size.width >= size.width && mode_size.height >= size.height
+    });
+
+    let closest = fitting.into_iter().min_by_key(|mode| {
+        let (size, refresh_rate) = describe(mode);
+        (area(size), Reverse(refresh_rate))
+    });
+
+    closest.or_else(|| {
+        smaller.into_iter().max_by_key(|mode| {
+            let (size, refresh_rate) = describe(mode);
+            (area(size), refresh_rate)
+        })
+    })
+}
+
+/// Grabs the cursor, falling back to the other grab mode if `grab` is not supported on this platform.
+pub(crate) fn set_cursor_grab(window: &Window, grab: CursorGrab) {
+    let modes: &[CursorGrabMode] = match grab {
+        CursorGrab::None => &[CursorGrabMode::None],
+        CursorGrab::Confined => &[CursorGrabMode::Confined, CursorGrabMode::Locked],
+        CursorGrab::Locked => &[CursorGrabMode::Locked, CursorGrabMode::Confined],
+    };
+
+    for &mode in modes {
+        match window.set_cursor_grab(mode) {
+            Ok(()) => return,
+            Err(why) => log::debug!("Failed to set cursor grab to {mode:?}: {why}"),
+        }
+    }
+
+    log::warn!("Cursor grab {grab:?} is not supported");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Video modes as `(width, height, refresh rate in millihertz)`.
+    fn best(modes: &[(u32, u32, u32)], width: u32, height: u32) -> Option<(u32, u32, u32)> {
+        best_video_mode(
+            modes.iter().copied(),
+            PhysicalSize::new(width, height),
+            |&(width, height, refresh_rate)| (PhysicalSize::new(width, height), refresh_rate),
+        )
+    }
+
+    #[test]
+    fn picks_smallest_fitting_mode() {
+        let modes = [
+            (1920, 1080, 60_000),
+            (1280, 720, 60_000),
+            (1280, 720, 144_000),
+            (1024, 768, 60_000),
+            (800, 600, 60_000),
+        ];
+
+        assert_eq!(best(&modes, 1280, 720), Some((1280, 720, 144_000)));
+        assert_eq!(best(&modes, 800, 600), Some((800, 600, 60_000)));
+        // 1280x720 is smaller, but too short.
+        assert_eq!(best(&modes, 1000, 750), Some((1024, 768, 60_000)));
+    }
+
+    #[test]
+    fn falls_back_to_largest_mode() {
+        let modes = [
+            (1280, 720, 60_000),
+            (1920, 1080, 30_000),
+            (1920, 1080, 60_000),
+        ];
+
+        assert_eq!(best(&modes, 2560, 1440), Some((1920, 1080, 60_000)));
+        assert_eq!(best(&[], 640, 480), None);
+    }
+}
@@ -0,0 +1,206 @@
+//! Golden-image tests, which render on a software adapter and compare against the references in `tests/golden`.
+//! Run with `KAFFEE_BLESS=1` to update the references after an intended change.
+
+use std::sync::Mutex;
+
+use image::{Rgba, RgbaImage};
+use kaffee::{prelude::*, testing};
+
+/// Graphics contexts are created one at a time, since some drivers do not support multiple instances at once.
+static GPU: Mutex<()> = Mutex::new(());
+
+const SIZE: u16 = 64;
+
+struct Draw<F>(F);
+
+impl<F: FnMut(&mut GfxContext)> EventHandler for Draw<F> {
+    fn init(&mut self, _: &mut GfxContext) -> Result<(), ErrorKind> {
+        Ok(())
+    }
+
+    fn input(&mut self, _: InputEvent) {}
+    fn update(&mut self, _: &mut GfxContext, _: f32) {}
+
+    fn redraw(&mut self, g: &mut GfxContext) {
+        g.clear_color(Color::BLACK);
+        (self.0)(g);
+    }
+}
+
+fn render(redraw: impl FnMut(&mut GfxContext)) -> RgbaImage {
+    let _gpu = GPU.lock().unwrap_or_else(|e| e.into_inner());
+    let config = Config {
+        width: SIZE,
+        height: SIZE,
+        force_fallback_adapter: true,
+        ..Config::default()
+    };
+
+    pollster::block_on(testing::run_frames(&mut Draw(redraw), &config, 1)).unwrap()
+}
+
+/// Returns the bounding box `(min_x, min_y, max_x, max_y)` of all pixels which are not black.
+fn bounds(image: &RgbaImage) -> Option<(u32, u32, u32, u32)> {
+    image
+        .enumerate_pixels()
+        .filter(|(_, _, pixel)| pixel.0[..3].iter().any(|&c| c > 0))
+        .fold(None, |bounds, (x, y, _)| match bounds {
+            None => Some((x, y, x, y)),
+            Some((x0, y0, x1, y1)) => Some((x0.min(x), y0.min(y), x1.max(x), y1.max(y))),
+        })
+}
+
+/// Checks a pixel of the sRGB frame against a linear color.
+fn is_color(pixel: &Rgba<u8>, color: Color) -> bool {
+    let expected = [color.r, color.g, color.b].map(|c| {
+        let srgb = if c <= 0.0031308 {
+            c * 12.92
+        } else {
+            1.055 * c.powf(1. / 2.4) - 0.055
+        };
+        (srgb * 255.).round() as u8
+    });
+    pixel.0[..3]
+        .iter()
+        .zip(expected)
+        .all(|(a, e)| a.abs_diff(e) <= 2)
+}
+
+#[test]
+fn draw_rectangle() {
+    let frame = render(|g| {
+        g.draw_rectangle(8., 8., 24., 16., Color::RED);
+        // Negative sizes flip the winding of the quad, which must not cull it.
+        g.draw_rectangle(56., 56., -16., -24., Color::BLUE);
+    });
+
+    assert!(is_color(frame.get_pixel(8, 8), Color::RED));
+    assert!(is_color(frame.get_pixel(31, 23), Color::RED));
+    assert!(is_color(frame.get_pixel(32, 8), Color::BLACK));
+    assert!(is_color(frame.get_pixel(8, 24), Color::BLACK));
+
+    assert!(is_color(frame.get_pixel(40, 32), Color::BLUE));
+    assert!(is_color(frame.get_pixel(55, 55), Color::BLUE));
+    assert!(is_color(frame.get_pixel(39, 32), Color::BLACK));
+    assert!(is_color(frame.get_pixel(40, 31), Color::BLACK));
+
+    testing::assert_golden(&frame, "tests/golden/draw_rectangle.png", 2);
+}
+
+#[test]
+fn draw_text() {
+    let frame = render(|g| g.draw_text(10., 20., 16., Color::WHITE, "kaffee"));
+
+    // Text is laid out from its top-left corner, so all glyphs lie below and to the right of the position.
+    let (min_x, min_y, max_x, max_y) = bounds(&frame).expect("No text was drawn");
+    assert!((10..14).contains(&min_x), "text starts at x = {min_x}");
+    assert!((20..26).contains(&min_y), "text starts at y = {min_y}");
+    assert!(max_x > 40, "text ends at x = {max_x}");
+    assert!(max_y < 20 + 16, "text ends at y = {max_y}");
+
+    testing::assert_golden(&frame, "tests/golden/draw_text.png", 16);
+}
+
+#[test]
+fn draw_texture() {
+    // A two-pixel texture checks the texture coordinates, a second texture drawn in between checks the passes.
+    let path =
+        |name: &str| std::env::temp_dir().join(format!("kaffee-{}-{name}.png", std::process::id()));
+    let (a, b) = (path("texture-a"), path("texture-b"));
+    RgbaImage::from_fn(2, 1, |x, _| {
+        if x == 0 {
+            Rgba([255, 0, 0, 255])
+        } else {
+            Rgba([0, 255, 0, 255])
+        }
+    })
+    .save(&a)
+    .unwrap();
+    RgbaImage::from_pixel(1, 1, Rgba([0, 0, 255, 255]))
+        .save(&b)
+        .unwrap();
+
+    let mut textures = None;
+    let frame = render(|g| {
+        let (a, b) = textures.get_or_insert_with(|| {
+            (
+                g.create_texture(&a, FilterMode::Nearest).unwrap(),
+                g.create_texture(&b, FilterMode::Nearest).unwrap(),
+            )
+        });
+        g.draw_texture(0., 0., 16., 16., Color::WHITE, a);
+        g.draw_texture(16., 0., 16., 16., Color::WHITE, b);
+        g.draw_texture(32., 0., 16., 16., Color::WHITE, a);
+    });
+    std::fs::remove_file(&a).unwrap();
+    std::fs::remove_file(&b).unwrap();
+
+    for x in [0, 32] {
+        assert_eq!(frame.get_pixel(x, 0).0, [255, 0, 0, 255]);
+        assert_eq!(frame.get_pixel(x + 15, 15).0, [0, 255, 0, 255]);
+    }
+    assert_eq!(frame.get_pixel(16, 0).0, [0, 0, 255, 255]);
+    assert_eq!(frame.get_pixel(31, 15).0, [0, 0, 255, 255]);
+    assert_eq!(bounds(&frame), Some((0, 0, 47, 15)));
+
+    testing::assert_golden(&frame, "tests/golden/draw_texture.png", 2);
+}
+
+#[test]
+fn clipped_viewports() {
+    let frame = render(|g| {
+        // The left half of this viewport lies off-screen. Its visible part must not be stretched.
+        g.set_camera(Camera2D {
+            viewport: Some(Rect::new(-32., 0., 64., 32.)),
+            ..Camera2D::default()
+        });
+        g.draw_rectangle(32., 0., 16., 16., Color::RED);
+
+        // Empty and off-screen viewports draw nothing.
+        for viewport in [Rect::new(8., 40., 0., 8.), Rect::new(100., 0., 10., 10.)] {
+            g.set_camera(Camera2D {
+                viewport: Some(viewport),
+                ..Camera2D::default()
+            });
+            g.draw_rectangle(0., 0., 64., 64., Color::BLUE);
+        }
+    });
+
+    assert!(is_color(frame.get_pixel(0, 0), Color::RED));
+    assert!(is_color(frame.get_pixel(15, 15), Color::RED));
+    assert!(is_color(frame.get_pixel(16, 0), Color::BLACK));
+    assert!(is_color(frame.get_pixel(0, 16), Color::BLACK));
+    assert_eq!(bounds(&frame), Some((0, 0, 15, 15)));
+}
+
+#[test]
+fn replay_resizes() {
+    let _gpu = GPU.lock().unwrap_or_else(|e| e.into_inner());
+    let config = Config {
+        width: SIZE,
+        height: SIZE,
+        force_fallback_adapter: true,
+        ..Config::default()
+    };
+
+    let mut recording = Recording::default();
+    recording.push_frame(
+        [RecordedEvent::Resized {
+            width: 32,
+            height: 16,
+            scale_factor: 2.,
+        }],
+        testing::FRAME_DT,
+    );
+
+    let mut sizes = Vec::new();
+    let frame = pollster::block_on(testing::replay(
+        &mut Draw(|g: &mut GfxContext| sizes.push((g.width(), g.height()))),
+        &config,
+        recording,
+    ))
+    .unwrap();
+
+    assert_eq!(frame.dimensions(), (32, 16));
+    assert_eq!(sizes, vec![(16., 8.)]);
+}